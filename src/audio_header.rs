@@ -0,0 +1,228 @@
+//! Reading the sample rate, length and loop points out of `.lopus` and `.idsp` headers, so a
+//! [`BgmPropertyEntry`] doesn't have to be measured by hand.
+//!
+//! `.lopus` is the Switch's Opus container: a `0x80000001` header chunk, then a `0x80000004` data chunk
+//! of packets each prefixed with their big endian size and final range. Smash's wrap it in a little
+//! endian Namco `OPUS` header with the sample count and loop points, the Switch header right after it.
+//! A bare one has neither, so it doesn't loop and its samples are counted from the packets.
+//! `.idsp` is a big endian `IDSP` header with the sample count and loop points right in it.
+//!
+//! ```ignore
+//! let entry = audio_header::bgm_property_from_audio("bgm_custom", &std::fs::read("custom.lopus")?)?;
+//! the_csk_collection_api::add_new_bgm_property_entry(&entry);
+//! ```
+use std::fmt;
+
+use smash_bgm_property::BgmPropertyEntry;
+
+use crate::stream::TimePoint;
+
+const OPUS_HEADER: u32 = 0x80000001;
+const OPUS_DATA: u32 = 0x80000004;
+
+// Where the Switch header starts in a Namco wrapped one
+const NAMCO_OPUS_SIZE: usize = 0x30;
+
+// Opus packets are counted in samples at this rate whatever the stream's rate is
+const OPUS_RATE: u64 = 48000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Opus,
+    Idsp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioHeaderError {
+    /// Neither an Opus nor an IDSP header. Holds the first bytes read.
+    UnknownFormat(Vec<u8>),
+    /// The data ended at `offset` before everything was read.
+    Truncated { offset: u64 },
+    MissingChunk(u32),
+    ZeroSampleRate,
+    /// The Opus packet at `offset` is empty or has no frame count.
+    InvalidPacket { offset: u64 },
+    InvalidLoop { start: u32, end: u32, total_samples: u32 },
+}
+
+impl fmt::Display for AudioHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioHeaderError::UnknownFormat(magic) => {
+                write!(f, "Your audio is neither lopus nor idsp! Read: {:02X?}", magic)
+            }
+            AudioHeaderError::Truncated { offset } => write!(f, "Your audio ends too early at {:#x}!", offset),
+            AudioHeaderError::MissingChunk(id) => write!(f, "Did not read the {:#010x} chunk of your lopus!", id),
+            AudioHeaderError::ZeroSampleRate => write!(f, "Your audio has a sample rate of 0!"),
+            AudioHeaderError::InvalidPacket { offset } => {
+                write!(f, "Failed reading the opus packet at {:#x}! Your lopus may be malformed.", offset)
+            }
+            AudioHeaderError::InvalidLoop { start, end, total_samples } => write!(
+                f,
+                "Your audio loops from sample {} to {}, which doesn't fit in its {} samples!",
+                start, end, total_samples
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AudioHeaderError {}
+
+fn bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], AudioHeaderError> {
+    offset
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .map(|end| &data[offset..end])
+        .ok_or(AudioHeaderError::Truncated { offset: offset as u64 })
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Result<u32, AudioHeaderError> {
+    Ok(u32::from_le_bytes(bytes(data, offset, 4)?.try_into().unwrap()))
+}
+
+fn i32_le(data: &[u8], offset: usize) -> Result<i32, AudioHeaderError> {
+    Ok(i32::from_le_bytes(bytes(data, offset, 4)?.try_into().unwrap()))
+}
+
+fn u32_be(data: &[u8], offset: usize) -> Result<u32, AudioHeaderError> {
+    Ok(u32::from_be_bytes(bytes(data, offset, 4)?.try_into().unwrap()))
+}
+
+fn u16_le(data: &[u8], offset: usize) -> Result<u16, AudioHeaderError> {
+    Ok(u16::from_le_bytes(bytes(data, offset, 2)?.try_into().unwrap()))
+}
+
+/// What a stream's header says about its length and loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioInfo {
+    pub format: AudioFormat,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub total_samples: u32,
+    /// The loop's start and end sample, if it loops.
+    pub loop_points: Option<(u32, u32)>,
+}
+
+impl AudioInfo {
+    /// Reads either format, told apart by their magic. A loop ending past the last sample ends on it.
+    pub fn parse(data: &[u8]) -> Result<Self, AudioHeaderError> {
+        let mut info = match bytes(data, 0, 4)? {
+            b"IDSP" => Self::parse_idsp(data)?,
+            b"OPUS" => Self::parse_namco_opus(data)?,
+            magic if u32::from_le_bytes(magic.try_into().unwrap()) == OPUS_HEADER => Self::parse_opus(data, 0)?,
+            magic => return Err(AudioHeaderError::UnknownFormat(magic.to_vec())),
+        };
+        if info.sample_rate == 0 {
+            return Err(AudioHeaderError::ZeroSampleRate);
+        }
+        if let Some((start, end)) = info.loop_points {
+            let end = end.min(info.total_samples);
+            if start >= end {
+                return Err(AudioHeaderError::InvalidLoop { start, end, total_samples: info.total_samples });
+            }
+            info.loop_points = Some((start, end));
+        }
+        Ok(info)
+    }
+
+    fn parse_idsp(data: &[u8]) -> Result<Self, AudioHeaderError> {
+        let loop_start = u32_be(data, 0x14)?;
+        let loop_end = u32_be(data, 0x18)?;
+        Ok(AudioInfo {
+            format: AudioFormat::Idsp,
+            channels: u32_be(data, 0x08)?,
+            sample_rate: u32_be(data, 0x0C)?,
+            total_samples: u32_be(data, 0x10)?,
+            // An end of 0 is how a stream that doesn't loop is written
+            loop_points: (loop_end != 0).then_some((loop_start, loop_end)),
+        })
+    }
+
+    fn parse_namco_opus(data: &[u8]) -> Result<Self, AudioHeaderError> {
+        let total_samples = u32_le(data, 0x08)?;
+        let loop_start = i32_le(data, 0x10)?;
+        let loop_end = i32_le(data, 0x14)?;
+        if u32_le(data, NAMCO_OPUS_SIZE)? != OPUS_HEADER {
+            return Err(AudioHeaderError::MissingChunk(OPUS_HEADER));
+        }
+        Ok(AudioInfo {
+            total_samples,
+            // Files that don't loop have -1 for both, or 0 like IDSP
+            loop_points: (loop_end > 0).then_some((loop_start.max(0) as u32, loop_end as u32)),
+            ..Self::parse_opus(data, NAMCO_OPUS_SIZE)?
+        })
+    }
+
+    /// Reads the Switch header at `base`, which its chunk offsets are relative to.
+    fn parse_opus(data: &[u8], base: usize) -> Result<Self, AudioHeaderError> {
+        let channels = bytes(data, base + 0x09, 1)?[0] as u32;
+        let sample_rate = u32_le(data, base + 0x0C)?;
+        let data_offset = base + u32_le(data, base + 0x10)? as usize;
+        let pre_skip = u16_le(data, base + 0x1C)? as u64;
+
+        if u32_le(data, data_offset)? != OPUS_DATA {
+            return Err(AudioHeaderError::MissingChunk(OPUS_DATA));
+        }
+        let data_size = u32_le(data, data_offset + 4)? as usize;
+        let start = data_offset + 8;
+        let end = start + bytes(data, start, data_size)?.len();
+
+        let mut samples = 0u64;
+        let mut pos = start;
+        while pos < end {
+            let packet_size = u32_be(data, pos)? as usize;
+            // The final range after the size is only for checking the decoder
+            let packet = bytes(data, pos + 8, packet_size)?;
+            samples += packet_samples(packet).ok_or(AudioHeaderError::InvalidPacket { offset: pos as u64 })?;
+            pos += 8 + packet_size;
+        }
+        // Packets always count at 48kHz and the pre-skip isn't played
+        let total_samples = (samples.saturating_sub(pre_skip) * sample_rate as u64 / OPUS_RATE) as u32;
+
+        Ok(AudioInfo { format: AudioFormat::Opus, channels, sample_rate, total_samples, loop_points: None })
+    }
+
+    pub fn duration(&self) -> TimePoint {
+        TimePoint::from_samples(self.total_samples as u64, self.sample_rate)
+    }
+
+    /// The entry for `stream_name` (e.g. `"bgm_custom"`). A stream that doesn't loop loops over all of it.
+    pub fn bgm_property(&self, stream_name: &str) -> BgmPropertyEntry {
+        let (loop_start, loop_end) = self.loop_points.unwrap_or((0, self.total_samples));
+        let millis = |sample: u32| TimePoint::from_samples(sample as u64, self.sample_rate).as_millis() as u32;
+        BgmPropertyEntry {
+            stream_name: smash_bgm_property::Hash40(crate::hash40::add_label(stream_name).0),
+            loop_start_ms: millis(loop_start),
+            loop_start_sample: loop_start,
+            loop_end_ms: millis(loop_end),
+            loop_end_sample: loop_end,
+            duration_ms: millis(self.total_samples),
+            duration_sample: self.total_samples,
+        }
+    }
+}
+
+/// How many samples at 48kHz an Opus packet decodes to, from its TOC byte.
+fn packet_samples(packet: &[u8]) -> Option<u64> {
+    let toc = *packet.first()?;
+    let config = toc >> 3;
+    let frame_samples = match config {
+        // SILK: 10, 20, 40 or 60ms
+        0..=11 => [480, 960, 1920, 2880][config as usize % 4],
+        // Hybrid: 10 or 20ms
+        12..=15 => [480, 960][config as usize % 2],
+        // CELT: 2.5, 5, 10 or 20ms
+        _ => [120, 240, 480, 960][config as usize % 4],
+    };
+    let frames = match toc & 0b11 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => (*packet.get(1)? & 0x3F) as u64,
+    };
+    (frames != 0).then_some(frame_samples * frames)
+}
+
+/// Reads a `.lopus` or `.idsp` and fills a [`BgmPropertyEntry`] for `stream_name` with its loop and length.
+pub fn bgm_property_from_audio(stream_name: &str, data: &[u8]) -> Result<BgmPropertyEntry, AudioHeaderError> {
+    Ok(AudioInfo::parse(data)?.bgm_property(stream_name))
+}
@@ -0,0 +1,264 @@
+//! Everything the CSK Collection plugin exports, behind a trait.
+//!
+//! The public functions in the crate root go through [`with_backend`]. Unless [`set_backend`] picked
+//! another one for the thread, that's the [`PluginBackend`], calling the real symbols. The `mock` feature
+//! adds [`mock::MockBackend`] to pick instead, so mod logic can be tested with `cargo test` on desktop.
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::rc::Rc;
+
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, MiiBodyDatabaseEntry, MiiHatDatabaseEntry,
+    SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, TipsDatabaseEntry, UiStageData,
+    Version,
+};
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub trait CskBackend {
+    fn play_bgm(&self, ui_bgm_hash: u64);
+    fn get_color_from_entry_id(&self, entry_id: u32) -> u32;
+    fn change_entry_chara_ui(&self, entry_id: u32, ui_chara_hash: u64, color_slot: u8);
+    fn get_ui_chara_from_entry_id(&self, entry_id: u32) -> u64;
+    fn get_victor_color(&self) -> u8;
+    fn allow_ui_chara_hash_online(&self, ui_chara_hash: u64);
+    fn disable_ui_chara_hash_online(&self, ui_chara_hash: u64);
+    fn is_online(&self) -> bool;
+    fn csk_collection_version(&self) -> Option<Version>;
+    fn add_narration_characall_entry(&self, entry: &str) -> bool;
+    fn set_fighter_jingle(&self, chara_id: u64, entry: &str);
+    fn load_ui_file(&self, ui_path: u64);
+
+    fn add_chara_db_entry_info(&self, entry: &CharacterDatabaseEntry);
+    fn add_chara_layout_db_entry_info(&self, entry: &CharacterLayoutDatabaseEntry);
+    fn add_series_db_entry_info(&self, entry: &SeriesDatabaseEntry);
+    fn add_bgm_db_entry_info(&self, entry: &BgmDatabaseRootEntry);
+    fn add_stream_set_entry_info(&self, entry: &StreamSetEntry);
+    fn add_assigned_info_entry_info(&self, entry: &AssignedInfoEntry);
+    fn add_stream_property_entry_info(&self, entry: &StreamPropertyEntry);
+    fn add_new_sli_entry(&self, entry: &smash_sli::SliEntry);
+    fn add_new_bgm_property_entry(&self, entry: &smash_bgm_property::BgmPropertyEntry);
+    // The plugin takes a &Vec over the Rust ABI, so the trait keeps it
+    #[allow(clippy::ptr_arg)]
+    fn add_tracks_to_playlist(&self, playlist: u64, tracks: &Vec<BgmPlaylistEntry>);
+    fn add_stage_db_entry(&self, entry: &StageDatabaseEntry);
+    fn add_ui_stage_db_resources_entry(&self, stage_place_id: u64, ui_stage_id: u64, stage_data: &UiStageData);
+    fn add_gametitle_db_entry_info(&self, entry: &GametitleDatabaseEntry);
+    fn add_tips_db_entry_info(&self, entry: &TipsDatabaseEntry);
+    fn add_amiibo_db_entry_info(&self, entry: &AmiiboDatabaseEntry);
+    fn add_mii_body_db_entry_info(&self, entry: &MiiBodyDatabaseEntry);
+    fn add_mii_hat_db_entry_info(&self, entry: &MiiHatDatabaseEntry);
+}
+
+// Declares the plugin's symbols. They only exist on console, so anywhere else each one is a stand-in that
+// panics, which keeps [`PluginBackend`] compiling and linking everywhere
+macro_rules! plugin_symbols {
+    ($($abi:literal { $(pub fn $name:ident($($arg:ident: $ty:ty),*) $(-> $ret:ty)?;)* })*) => {
+        #[cfg(target_os = "switch")]
+        mod externed {
+            $(extern $abi { $(pub fn $name($($arg: $ty),*) $(-> $ret)?;)* })*
+        }
+
+        #[cfg(not(target_os = "switch"))]
+        #[allow(unused_variables, clippy::ptr_arg)]
+        mod externed {
+            $($(pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                panic!("{} is only exported by the CSK Collection plugin on console!", stringify!($name))
+            })*)*
+        }
+    };
+}
+
+plugin_symbols! {
+    "C" {
+        pub fn play_bgm(ui_bgm_hash: u64);
+        pub fn get_color_from_entry_id(entry_id: u32) -> u32;
+        pub fn change_entry_chara_ui(entry_id: u32, ui_chara_hash: u64, color_slot: u8);
+        pub fn get_ui_chara_from_entry_id(entry_id: u32) -> u64;
+        pub fn get_victor_color() -> u8;
+        pub fn allow_ui_chara_hash_online(ui_chara_hash: u64);
+        pub fn disable_ui_chara_hash_online(ui_chara_hash: u64);
+        pub fn is_online() -> bool;
+        pub fn csk_collection_version() -> *const crate::Version;
+        pub fn add_narration_characall_entry(string_ptr: *mut i8) -> bool;
+        pub fn set_fighter_jingle(chara_id: u64, string_ptr: *mut i8);
+        pub fn load_ui_file(ui_path: u64);
+    }
+    "Rust" {
+        pub fn add_chara_db_entry_info(chara_db_entry_info: &crate::CharacterDatabaseEntry);
+        pub fn add_chara_layout_db_entry_info(chara_db_entry_info: &crate::CharacterLayoutDatabaseEntry);
+        pub fn add_series_db_entry_info(series_db_entry_info: &crate::SeriesDatabaseEntry);
+        pub fn add_bgm_db_entry_info(bgm_db_entry_info: &crate::BgmDatabaseRootEntry);
+        pub fn add_stream_set_entry_info(stream_set_entry_info: &crate::StreamSetEntry);
+        pub fn add_assigned_info_entry_info(assigned_info_entry_info: &crate::AssignedInfoEntry);
+        pub fn add_stream_property_entry_info(stream_property_entry_info: &crate::StreamPropertyEntry);
+        pub fn add_new_sli_entry(entry: &smash_sli::SliEntry);
+        pub fn add_new_bgm_property_entry(entry: &smash_bgm_property::BgmPropertyEntry);
+        pub fn add_tracks_to_playlist(playlist: u64, tracks: &Vec<crate::BgmPlaylistEntry>);
+        pub fn add_stage_db_entry(stage_entry: &crate::StageDatabaseEntry);
+        pub fn add_ui_stage_db_resources_entry(stage_place_id: u64, ui_stage_id: u64, stage_data: &crate::UiStageData);
+        pub fn add_gametitle_db_entry_info(gametitle_db_entry_info: &crate::GametitleDatabaseEntry);
+        pub fn add_tips_db_entry_info(tips_db_entry_info: &crate::TipsDatabaseEntry);
+        pub fn add_amiibo_db_entry_info(amiibo_db_entry_info: &crate::AmiiboDatabaseEntry);
+        pub fn add_mii_body_db_entry_info(mii_body_db_entry_info: &crate::MiiBodyDatabaseEntry);
+        pub fn add_mii_hat_db_entry_info(mii_hat_db_entry_info: &crate::MiiHatDatabaseEntry);
+    }
+}
+
+// The plugin takes ownership of the strings in the entries it's given and frees them itself, so it gets a copy
+// whose strings are handed off once the call returns. The rest of the copy is dropped here as usual
+fn hand_off<T: Clone + crate::HandOff>(entry: &T, f: impl FnOnce(&T)) {
+    let mut entry = entry.clone();
+    f(&entry);
+    entry.hand_off();
+}
+
+/// Forwards every call to the symbols exported by the CSK Collection plugin.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PluginBackend;
+
+impl CskBackend for PluginBackend {
+    fn play_bgm(&self, ui_bgm_hash: u64) {
+        unsafe { externed::play_bgm(ui_bgm_hash) }
+    }
+
+    fn get_color_from_entry_id(&self, entry_id: u32) -> u32 {
+        unsafe { externed::get_color_from_entry_id(entry_id) }
+    }
+
+    fn change_entry_chara_ui(&self, entry_id: u32, ui_chara_hash: u64, color_slot: u8) {
+        unsafe { externed::change_entry_chara_ui(entry_id, ui_chara_hash, color_slot) }
+    }
+
+    fn get_ui_chara_from_entry_id(&self, entry_id: u32) -> u64 {
+        unsafe { externed::get_ui_chara_from_entry_id(entry_id) }
+    }
+
+    fn get_victor_color(&self) -> u8 {
+        unsafe { externed::get_victor_color() }
+    }
+
+    fn allow_ui_chara_hash_online(&self, ui_chara_hash: u64) {
+        unsafe { externed::allow_ui_chara_hash_online(ui_chara_hash) }
+    }
+
+    fn disable_ui_chara_hash_online(&self, ui_chara_hash: u64) {
+        unsafe { externed::disable_ui_chara_hash_online(ui_chara_hash) }
+    }
+
+    fn is_online(&self) -> bool {
+        unsafe { externed::is_online() }
+    }
+
+    fn csk_collection_version(&self) -> Option<Version> {
+        unsafe { externed::csk_collection_version().as_ref().copied() }
+    }
+
+    // The plugin copies these strings, so they're freed once the call returns
+    fn add_narration_characall_entry(&self, entry: &str) -> bool {
+        let entry = CString::new(entry).unwrap_or_else(|_| panic!("Failed converting {} to CString!", entry));
+        unsafe { externed::add_narration_characall_entry(entry.as_ptr() as _) }
+    }
+
+    fn set_fighter_jingle(&self, chara_id: u64, entry: &str) {
+        let entry = CString::new(entry).unwrap_or_else(|_| panic!("Failed converting {} to CString!", entry));
+        unsafe { externed::set_fighter_jingle(chara_id, entry.as_ptr() as _) }
+    }
+
+    fn load_ui_file(&self, ui_path: u64) {
+        unsafe { externed::load_ui_file(ui_path) }
+    }
+
+    fn add_chara_db_entry_info(&self, entry: &CharacterDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_chara_db_entry_info(entry) })
+    }
+
+    fn add_chara_layout_db_entry_info(&self, entry: &CharacterLayoutDatabaseEntry) {
+        unsafe { externed::add_chara_layout_db_entry_info(entry) }
+    }
+
+    fn add_series_db_entry_info(&self, entry: &SeriesDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_series_db_entry_info(entry) })
+    }
+
+    fn add_bgm_db_entry_info(&self, entry: &BgmDatabaseRootEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_bgm_db_entry_info(entry) })
+    }
+
+    fn add_stream_set_entry_info(&self, entry: &StreamSetEntry) {
+        unsafe { externed::add_stream_set_entry_info(entry) }
+    }
+
+    fn add_assigned_info_entry_info(&self, entry: &AssignedInfoEntry) {
+        unsafe { externed::add_assigned_info_entry_info(entry) }
+    }
+
+    fn add_stream_property_entry_info(&self, entry: &StreamPropertyEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_stream_property_entry_info(entry) })
+    }
+
+    fn add_new_sli_entry(&self, entry: &smash_sli::SliEntry) {
+        unsafe { externed::add_new_sli_entry(entry) }
+    }
+
+    fn add_new_bgm_property_entry(&self, entry: &smash_bgm_property::BgmPropertyEntry) {
+        unsafe { externed::add_new_bgm_property_entry(entry) }
+    }
+
+    fn add_tracks_to_playlist(&self, playlist: u64, tracks: &Vec<BgmPlaylistEntry>) {
+        unsafe { externed::add_tracks_to_playlist(playlist, tracks) }
+    }
+
+    fn add_stage_db_entry(&self, entry: &StageDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_stage_db_entry(entry) })
+    }
+
+    fn add_ui_stage_db_resources_entry(&self, stage_place_id: u64, ui_stage_id: u64, stage_data: &UiStageData) {
+        unsafe { externed::add_ui_stage_db_resources_entry(stage_place_id, ui_stage_id, stage_data) }
+    }
+
+    fn add_gametitle_db_entry_info(&self, entry: &GametitleDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_gametitle_db_entry_info(entry) })
+    }
+
+    fn add_tips_db_entry_info(&self, entry: &TipsDatabaseEntry) {
+        unsafe { externed::add_tips_db_entry_info(entry) }
+    }
+
+    fn add_amiibo_db_entry_info(&self, entry: &AmiiboDatabaseEntry) {
+        unsafe { externed::add_amiibo_db_entry_info(entry) }
+    }
+
+    fn add_mii_body_db_entry_info(&self, entry: &MiiBodyDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_mii_body_db_entry_info(entry) })
+    }
+
+    fn add_mii_hat_db_entry_info(&self, entry: &MiiHatDatabaseEntry) {
+        hand_off(entry, |entry| unsafe { externed::add_mii_hat_db_entry_info(entry) })
+    }
+}
+
+thread_local! {
+    static BACKEND: RefCell<Option<Rc<dyn CskBackend>>> = RefCell::new(None);
+}
+
+/// Makes the crate root functions called on this thread use `backend` instead of the plugin.
+pub fn set_backend(backend: Rc<dyn CskBackend>) {
+    BACKEND.with(|current| *current.borrow_mut() = Some(backend));
+}
+
+/// Goes back to the [`PluginBackend`] on this thread.
+pub fn reset_backend() {
+    BACKEND.with(|current| *current.borrow_mut() = None);
+}
+
+/// Runs `f` with the backend the crate root functions use.
+pub fn with_backend<R>(f: impl FnOnce(&dyn CskBackend) -> R) -> R {
+    // Cloned out so `f` can still change the backend
+    match BACKEND.with(|current| current.borrow().clone()) {
+        Some(backend) => f(&*backend),
+        None => f(&PluginBackend),
+    }
+}
@@ -0,0 +1,265 @@
+//! An in-memory [`CskBackend`] for testing mods off console.
+//!
+//! Every call is recorded by name and every registered entry is kept, so a test can run mod setup code
+//! through the normal crate functions and then look at what it did:
+//!
+//! ```ignore
+//! mock::install();
+//! the_csk_collection_api::add_narration_characall_entry("vc_narration_characall_custom");
+//! with_mock(|mock| assert_eq!(mock.state().narration_characall, ["vc_narration_characall_custom"]));
+//! ```
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::CskBackend;
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, MiiBodyDatabaseEntry, MiiHatDatabaseEntry,
+    SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, TipsDatabaseEntry, UiStageData,
+    Version,
+};
+
+/// Everything the mock has been told so far, plus the values its getters hand back.
+#[derive(Debug)]
+pub struct MockState {
+    // Names of the backend functions called, in order
+    pub calls: Vec<&'static str>,
+
+    pub chara_db: Vec<CharacterDatabaseEntry>,
+    pub chara_layout_db: Vec<CharacterLayoutDatabaseEntry>,
+    pub series_db: Vec<SeriesDatabaseEntry>,
+    pub bgm_db: Vec<BgmDatabaseRootEntry>,
+    pub stream_set: Vec<StreamSetEntry>,
+    pub assigned_info: Vec<AssignedInfoEntry>,
+    pub stream_property: Vec<StreamPropertyEntry>,
+    pub sli: Vec<smash_sli::SliEntry>,
+    pub bgm_property: Vec<smash_bgm_property::BgmPropertyEntry>,
+    pub playlists: HashMap<u64, Vec<BgmPlaylistEntry>>,
+    pub stage_db: Vec<StageDatabaseEntry>,
+    // (stage_place_id, ui_stage_id, data)
+    pub ui_stage_resources: Vec<(u64, u64, UiStageData)>,
+    pub gametitle_db: Vec<GametitleDatabaseEntry>,
+    pub tips_db: Vec<TipsDatabaseEntry>,
+    pub amiibo_db: Vec<AmiiboDatabaseEntry>,
+    pub mii_body_db: Vec<MiiBodyDatabaseEntry>,
+    pub mii_hat_db: Vec<MiiHatDatabaseEntry>,
+    pub narration_characall: Vec<String>,
+    pub fighter_jingles: HashMap<u64, String>,
+    pub online_charas: HashSet<u64>,
+    pub loaded_ui_files: Vec<u64>,
+    pub playing_bgm: Option<u64>,
+
+    // entry_id -> (ui_chara_hash, color_slot)
+    pub entries: HashMap<u32, (u64, u8)>,
+    pub victor_color: u8,
+    pub online: bool,
+    // None acts like a plugin that doesn't report its version
+    pub version: Option<Version>,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        MockState {
+            calls: Vec::new(),
+            chara_db: Vec::new(),
+            chara_layout_db: Vec::new(),
+            series_db: Vec::new(),
+            bgm_db: Vec::new(),
+            stream_set: Vec::new(),
+            assigned_info: Vec::new(),
+            stream_property: Vec::new(),
+            sli: Vec::new(),
+            bgm_property: Vec::new(),
+            playlists: HashMap::new(),
+            stage_db: Vec::new(),
+            ui_stage_resources: Vec::new(),
+            gametitle_db: Vec::new(),
+            tips_db: Vec::new(),
+            amiibo_db: Vec::new(),
+            mii_body_db: Vec::new(),
+            mii_hat_db: Vec::new(),
+            narration_characall: Vec::new(),
+            fighter_jingles: HashMap::new(),
+            online_charas: HashSet::new(),
+            loaded_ui_files: Vec::new(),
+            playing_bgm: None,
+            entries: HashMap::new(),
+            victor_color: 0,
+            online: false,
+            // Newer than any release, so every API counts as available
+            version: Some(Version { major: u32::MAX, minor: 0, patch: 0 }),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    state: RefCell<MockState>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        MockBackend::default()
+    }
+
+    pub fn state(&self) -> Ref<'_, MockState> {
+        self.state.borrow()
+    }
+
+    /// For setting up what the getters return, e.g. `state_mut().online = true`.
+    pub fn state_mut(&self) -> RefMut<'_, MockState> {
+        self.state.borrow_mut()
+    }
+
+    /// Forgets every call and entry.
+    pub fn reset(&self) {
+        *self.state.borrow_mut() = MockState::default();
+    }
+
+    fn record(&self, call: &'static str) -> RefMut<'_, MockState> {
+        let mut state = self.state.borrow_mut();
+        state.calls.push(call);
+        state
+    }
+}
+
+thread_local! {
+    static MOCK: Rc<MockBackend> = Rc::new(MockBackend::new());
+}
+
+/// Makes the crate root functions called on this thread use this thread's mock.
+/// Tests run on their own threads, so they don't see each other's entries.
+pub fn install() {
+    super::set_backend(MOCK.with(|mock| mock.clone()));
+}
+
+/// Runs `f` with this thread's mock, the one [`install`] hands to the crate root functions.
+pub fn with_mock<R>(f: impl FnOnce(&MockBackend) -> R) -> R {
+    MOCK.with(|mock| f(mock))
+}
+
+impl CskBackend for MockBackend {
+    fn play_bgm(&self, ui_bgm_hash: u64) {
+        self.record("play_bgm").playing_bgm = Some(ui_bgm_hash);
+    }
+
+    fn get_color_from_entry_id(&self, entry_id: u32) -> u32 {
+        self.record("get_color_from_entry_id").entries.get(&entry_id).map_or(0, |entry| entry.1 as u32)
+    }
+
+    fn change_entry_chara_ui(&self, entry_id: u32, ui_chara_hash: u64, color_slot: u8) {
+        self.record("change_entry_chara_ui").entries.insert(entry_id, (ui_chara_hash, color_slot));
+    }
+
+    fn get_ui_chara_from_entry_id(&self, entry_id: u32) -> u64 {
+        self.record("get_ui_chara_from_entry_id").entries.get(&entry_id).map_or(0, |entry| entry.0)
+    }
+
+    fn get_victor_color(&self) -> u8 {
+        self.record("get_victor_color").victor_color
+    }
+
+    fn allow_ui_chara_hash_online(&self, ui_chara_hash: u64) {
+        self.record("allow_ui_chara_hash_online").online_charas.insert(ui_chara_hash);
+    }
+
+    fn disable_ui_chara_hash_online(&self, ui_chara_hash: u64) {
+        self.record("disable_ui_chara_hash_online").online_charas.remove(&ui_chara_hash);
+    }
+
+    fn is_online(&self) -> bool {
+        self.record("is_online").online
+    }
+
+    fn csk_collection_version(&self) -> Option<Version> {
+        self.record("csk_collection_version").version
+    }
+
+    fn add_narration_characall_entry(&self, entry: &str) -> bool {
+        self.record("add_narration_characall_entry").narration_characall.push(entry.to_string());
+        true
+    }
+
+    fn set_fighter_jingle(&self, chara_id: u64, entry: &str) {
+        self.record("set_fighter_jingle").fighter_jingles.insert(chara_id, entry.to_string());
+    }
+
+    fn load_ui_file(&self, ui_path: u64) {
+        self.record("load_ui_file").loaded_ui_files.push(ui_path);
+    }
+
+    fn add_chara_db_entry_info(&self, entry: &CharacterDatabaseEntry) {
+        self.record("add_chara_db_entry_info").chara_db.push(entry.clone());
+    }
+
+    fn add_chara_layout_db_entry_info(&self, entry: &CharacterLayoutDatabaseEntry) {
+        self.record("add_chara_layout_db_entry_info").chara_layout_db.push(entry.clone());
+    }
+
+    fn add_series_db_entry_info(&self, entry: &SeriesDatabaseEntry) {
+        self.record("add_series_db_entry_info").series_db.push(entry.clone());
+    }
+
+    fn add_bgm_db_entry_info(&self, entry: &BgmDatabaseRootEntry) {
+        self.record("add_bgm_db_entry_info").bgm_db.push(entry.clone());
+    }
+
+    fn add_stream_set_entry_info(&self, entry: &StreamSetEntry) {
+        self.record("add_stream_set_entry_info").stream_set.push(entry.clone());
+    }
+
+    fn add_assigned_info_entry_info(&self, entry: &AssignedInfoEntry) {
+        self.record("add_assigned_info_entry_info").assigned_info.push(entry.clone());
+    }
+
+    fn add_stream_property_entry_info(&self, entry: &StreamPropertyEntry) {
+        self.record("add_stream_property_entry_info").stream_property.push(entry.clone());
+    }
+
+    fn add_new_sli_entry(&self, entry: &smash_sli::SliEntry) {
+        self.record("add_new_sli_entry").sli.push(entry.clone());
+    }
+
+    fn add_new_bgm_property_entry(&self, entry: &smash_bgm_property::BgmPropertyEntry) {
+        // BgmPropertyEntry isn't Clone
+        let entry = smash_bgm_property::BgmPropertyEntry { ..*entry };
+        self.record("add_new_bgm_property_entry").bgm_property.push(entry);
+    }
+
+    fn add_tracks_to_playlist(&self, playlist: u64, tracks: &Vec<BgmPlaylistEntry>) {
+        self.record("add_tracks_to_playlist").playlists.entry(playlist).or_default().extend(tracks.iter().cloned());
+    }
+
+    fn add_stage_db_entry(&self, entry: &StageDatabaseEntry) {
+        self.record("add_stage_db_entry").stage_db.push(entry.clone());
+    }
+
+    fn add_ui_stage_db_resources_entry(&self, stage_place_id: u64, ui_stage_id: u64, stage_data: &UiStageData) {
+        self.record("add_ui_stage_db_resources_entry").ui_stage_resources.push((
+            stage_place_id,
+            ui_stage_id,
+            stage_data.clone(),
+        ));
+    }
+
+    fn add_gametitle_db_entry_info(&self, entry: &GametitleDatabaseEntry) {
+        self.record("add_gametitle_db_entry_info").gametitle_db.push(entry.clone());
+    }
+
+    fn add_tips_db_entry_info(&self, entry: &TipsDatabaseEntry) {
+        self.record("add_tips_db_entry_info").tips_db.push(entry.clone());
+    }
+
+    fn add_amiibo_db_entry_info(&self, entry: &AmiiboDatabaseEntry) {
+        self.record("add_amiibo_db_entry_info").amiibo_db.push(entry.clone());
+    }
+
+    fn add_mii_body_db_entry_info(&self, entry: &MiiBodyDatabaseEntry) {
+        self.record("add_mii_body_db_entry_info").mii_body_db.push(entry.clone());
+    }
+
+    fn add_mii_hat_db_entry_info(&self, entry: &MiiHatDatabaseEntry) {
+        self.record("add_mii_hat_db_entry_info").mii_hat_db.push(entry.clone());
+    }
+}
@@ -0,0 +1,479 @@
+//! Registering a set of entries all at once, or not at all.
+//!
+//! Calling the `add_*` functions one at a time leaves the game half modded if a later call panics,
+//! e.g. on a jingle with a NUL byte. A [`Batch`] collects entries of every kind and checks them
+//! together before anything is registered: every entry is validated, ids and slots are checked
+//! against each other and everything added before, and every id an entry refers to (its `clone_from_*`,
+//! a bgm's `stream_set_id`, a layout's `ui_chara_id`, ...) has to be in the batch, added before or in the game.
+//! The plugin can't be asked what the game has, so references to vanilla entries are only checked for
+//! the tables whose params were given to [`crate::registry::record_params`].
+//!
+//! ```ignore
+//! let mut batch = Batch::new();
+//! batch.add(stream_property).add(assigned_info).add(stream_set).add(bgm);
+//! batch.add_tracks_to_playlist(hash40("bgmmario").0, vec![track]);
+//! if let Err(err) = batch.commit() {
+//!     println!("{}", err);
+//! }
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::registry::{Conflict, Registry, Table, Tracked};
+use crate::validate::{Diagnostic, Severity, Validate};
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, Hash40, Hash40Type, MiiBodyDatabaseEntry,
+    MiiHatDatabaseEntry, SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry,
+    TipsDatabaseEntry, UiStageData,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchIssue {
+    /// An error from the entry's [`Validate`] impl. Warnings don't stop a commit.
+    Invalid(Diagnostic),
+    /// With another entry in the batch, or one added before it.
+    Conflict(Conflict),
+    /// `field` refers to `reference`, which isn't in `table` or the batch.
+    MissingReference { entry: &'static str, id: Hash40, field: &'static str, table: Table, reference: Hash40 },
+    /// A string the plugin takes as a C string has a NUL byte in it.
+    NulInString { what: &'static str, value: String },
+}
+
+impl fmt::Display for BatchIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchIssue::Invalid(diagnostic) => write!(f, "{}", diagnostic),
+            BatchIssue::Conflict(conflict) => write!(f, "{}", conflict),
+            BatchIssue::MissingReference { entry, id, field, table, reference } => {
+                write!(f, "{} {} {}: {} isn't in {} or the batch", entry, id, field, reference, table.name())
+            }
+            BatchIssue::NulInString { what, value } => write!(f, "{} {:?} can't contain a NUL byte", what, value),
+        }
+    }
+}
+
+/// Why a [`Batch`] wasn't committed. Nothing in it was registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchError {
+    pub issues: Vec<BatchIssue>,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The batch wasn't committed! Found {} issue(s):", self.issues.len())?;
+        for issue in self.issues.iter() {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+#[derive(Debug, Clone)]
+pub struct PlaylistTracks {
+    pub playlist: u64,
+    pub tracks: Vec<BgmPlaylistEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UiStageResources {
+    pub stage_place_id: u64,
+    pub ui_stage_id: u64,
+    pub data: UiStageData,
+}
+
+/// Entries to register together, see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct Batch {
+    pub series_db: Vec<SeriesDatabaseEntry>,
+    pub gametitle_db: Vec<GametitleDatabaseEntry>,
+    pub chara_db: Vec<CharacterDatabaseEntry>,
+    pub chara_layout_db: Vec<CharacterLayoutDatabaseEntry>,
+    pub online_charas: Vec<u64>,
+    pub narration_characall: Vec<String>,
+    // (ui_chara_id, jingle)
+    pub fighter_jingles: Vec<(u64, String)>,
+    pub stream_property: Vec<StreamPropertyEntry>,
+    pub assigned_info: Vec<AssignedInfoEntry>,
+    pub stream_set: Vec<StreamSetEntry>,
+    pub bgm_db: Vec<BgmDatabaseRootEntry>,
+    pub sli: Vec<smash_sli::SliEntry>,
+    pub bgm_property: Vec<smash_bgm_property::BgmPropertyEntry>,
+    pub playlists: Vec<PlaylistTracks>,
+    pub stage_db: Vec<StageDatabaseEntry>,
+    pub ui_stage_resources: Vec<UiStageResources>,
+    pub tips_db: Vec<TipsDatabaseEntry>,
+    pub amiibo_db: Vec<AmiiboDatabaseEntry>,
+    pub mii_body_db: Vec<MiiBodyDatabaseEntry>,
+    pub mii_hat_db: Vec<MiiHatDatabaseEntry>,
+}
+
+/// The entry types [`Batch::add`] takes.
+pub trait BatchEntry {
+    fn add_to(self, batch: &mut Batch);
+}
+
+macro_rules! batch_entries {
+    ($($entry:ty => $field:ident;)*) => {
+        $(
+            impl BatchEntry for $entry {
+                fn add_to(self, batch: &mut Batch) {
+                    batch.$field.push(self);
+                }
+            }
+        )*
+    };
+}
+
+batch_entries! {
+    SeriesDatabaseEntry => series_db;
+    GametitleDatabaseEntry => gametitle_db;
+    CharacterDatabaseEntry => chara_db;
+    CharacterLayoutDatabaseEntry => chara_layout_db;
+    StreamPropertyEntry => stream_property;
+    AssignedInfoEntry => assigned_info;
+    StreamSetEntry => stream_set;
+    BgmDatabaseRootEntry => bgm_db;
+    StageDatabaseEntry => stage_db;
+    TipsDatabaseEntry => tips_db;
+    AmiiboDatabaseEntry => amiibo_db;
+    MiiBodyDatabaseEntry => mii_body_db;
+    MiiHatDatabaseEntry => mii_hat_db;
+}
+
+// Collects the references of every entry, resolving them once they're all known
+#[derive(Default)]
+struct References {
+    added: HashMap<Table, HashSet<u64>>,
+    wanted: Vec<(&'static str, u64, &'static str, Table, u64)>,
+}
+
+impl References {
+    fn add<T: Tracked>(&mut self, entry: &T) {
+        self.added.entry(T::TABLE).or_default().insert(entry.id());
+    }
+
+    fn want(&mut self, entry: &'static str, id: u64, field: &'static str, table: Table, reference: &Hash40Type) {
+        // 0 is the empty hash, which the game uses for "none"
+        if let Some(&reference) = reference.value().filter(|reference| **reference != 0) {
+            self.wanted.push((entry, id, field, table, reference));
+        }
+    }
+
+    // Checked against the registry, which knows what was added before and the vanilla ids it was given
+    fn missing(self, registry: &Registry) -> Vec<BatchIssue> {
+        let mut issues = Vec::new();
+        for (entry, id, field, table, reference) in self.wanted {
+            if self.added.get(&table).is_some_and(|added| added.contains(&reference))
+                || registry.contains(table, reference)
+                || registry.is_vanilla(table, reference)
+                || !registry.knows_vanilla(table)
+            {
+                continue;
+            }
+            issues.push(BatchIssue::MissingReference {
+                entry,
+                id: Hash40(id),
+                field,
+                table,
+                reference: Hash40(reference),
+            });
+        }
+        issues
+    }
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a database entry of any kind.
+    pub fn add<E: BatchEntry>(&mut self, entry: E) -> &mut Self {
+        entry.add_to(self);
+        self
+    }
+
+    pub fn allow_online(&mut self, ui_chara_id: u64) -> &mut Self {
+        self.online_charas.push(ui_chara_id);
+        self
+    }
+
+    pub fn add_narration_characall(&mut self, label: &str) -> &mut Self {
+        self.narration_characall.push(label.to_string());
+        self
+    }
+
+    pub fn set_fighter_jingle(&mut self, ui_chara_id: u64, jingle: &str) -> &mut Self {
+        self.fighter_jingles.push((ui_chara_id, jingle.to_string()));
+        self
+    }
+
+    pub fn add_sli(&mut self, entry: smash_sli::SliEntry) -> &mut Self {
+        self.sli.push(entry);
+        self
+    }
+
+    pub fn add_bgm_property(&mut self, entry: smash_bgm_property::BgmPropertyEntry) -> &mut Self {
+        self.bgm_property.push(entry);
+        self
+    }
+
+    pub fn add_tracks_to_playlist(&mut self, playlist: u64, tracks: Vec<BgmPlaylistEntry>) -> &mut Self {
+        self.playlists.push(PlaylistTracks { playlist, tracks });
+        self
+    }
+
+    pub fn add_ui_stage_resources(&mut self, stage_place_id: u64, ui_stage_id: u64, data: UiStageData) -> &mut Self {
+        self.ui_stage_resources.push(UiStageResources { stage_place_id, ui_stage_id, data });
+        self
+    }
+
+    /// Everything that would stop [`Batch::commit`], without registering anything.
+    pub fn check(&self) -> Vec<BatchIssue> {
+        let mut issues = Vec::new();
+        issues.extend(
+            self.validate().into_iter().filter(|diagnostic| diagnostic.severity == Severity::Error).map(BatchIssue::Invalid),
+        );
+
+        // Recorded into a copy, so a failed batch leaves the registry as it was
+        let mut registry = crate::registry::with_registry(|registry| registry.clone());
+        let mut conflicts = Vec::new();
+        conflicts.extend(self.series_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.gametitle_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.chara_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.chara_layout_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.stream_property.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.assigned_info.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.stream_set.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.bgm_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.stage_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.tips_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.amiibo_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.mii_body_db.iter().flat_map(|entry| registry.record(entry)));
+        conflicts.extend(self.mii_hat_db.iter().flat_map(|entry| registry.record(entry)));
+        issues.extend(conflicts.into_iter().map(BatchIssue::Conflict));
+
+        issues.extend(self.references().missing(&registry));
+
+        for label in self.narration_characall.iter().filter(|label| label.contains('\0')) {
+            issues.push(BatchIssue::NulInString { what: "narration_characall", value: label.clone() });
+        }
+        for (_, jingle) in self.fighter_jingles.iter().filter(|(_, jingle)| jingle.contains('\0')) {
+            issues.push(BatchIssue::NulInString { what: "fighter jingle", value: jingle.clone() });
+        }
+        issues
+    }
+
+    fn references(&self) -> References {
+        let mut references = References::default();
+        self.series_db.iter().for_each(|entry| references.add(entry));
+        self.gametitle_db.iter().for_each(|entry| references.add(entry));
+        self.chara_db.iter().for_each(|entry| references.add(entry));
+        self.chara_layout_db.iter().for_each(|entry| references.add(entry));
+        self.stream_property.iter().for_each(|entry| references.add(entry));
+        self.assigned_info.iter().for_each(|entry| references.add(entry));
+        self.stream_set.iter().for_each(|entry| references.add(entry));
+        self.bgm_db.iter().for_each(|entry| references.add(entry));
+        self.stage_db.iter().for_each(|entry| references.add(entry));
+        self.tips_db.iter().for_each(|entry| references.add(entry));
+        self.amiibo_db.iter().for_each(|entry| references.add(entry));
+        self.mii_body_db.iter().for_each(|entry| references.add(entry));
+        self.mii_hat_db.iter().for_each(|entry| references.add(entry));
+
+        // A clone needs the entry it's cloned from
+        macro_rules! want_clone_sources {
+            ($($field:ident: $entry:literal, $table:ident, $id:ident, $clone_from:ident;)*) => {
+                $(
+                    for entry in self.$field.iter() {
+                        if let Some(clone_from) = entry.$clone_from {
+                            let clone_from = Hash40Type::Overwrite(clone_from);
+                            references.want($entry, entry.$id, stringify!($clone_from), Table::$table, &clone_from);
+                        }
+                    }
+                )*
+            };
+        }
+        want_clone_sources! {
+            series_db: "SeriesDatabaseEntry", Series, ui_series_id, clone_from_ui_series_id;
+            gametitle_db: "GametitleDatabaseEntry", Gametitle, ui_gametitle_id, clone_from_ui_gametitle_id;
+            chara_db: "CharacterDatabaseEntry", Chara, ui_chara_id, clone_from_ui_chara_id;
+            chara_layout_db: "CharacterLayoutDatabaseEntry", CharaLayout, ui_layout_id, clone_from_ui_layout_id;
+            stream_property: "StreamPropertyEntry", StreamProperty, stream_id, clone_from_stream_id;
+            assigned_info: "AssignedInfoEntry", AssignedInfo, info_id, clone_from_info_id;
+            stream_set: "StreamSetEntry", StreamSet, stream_set_id, clone_from_stream_set_id;
+            bgm_db: "BgmDatabaseRootEntry", Bgm, ui_bgm_id, clone_from_ui_bgm_id;
+            stage_db: "StageDatabaseEntry", Stage, ui_stage_id, clone_from_ui_stage_id;
+            tips_db: "TipsDatabaseEntry", Tips, ui_tips_id, clone_from_ui_tips_id;
+            amiibo_db: "AmiiboDatabaseEntry", Amiibo, ui_amiibo_id, clone_from_ui_amiibo_id;
+            mii_body_db: "MiiBodyDatabaseEntry", MiiBody, ui_mii_body_id, clone_from_ui_mii_body_id;
+            mii_hat_db: "MiiHatDatabaseEntry", MiiHat, ui_mii_hat_id, clone_from_ui_mii_hat_id;
+        }
+
+        for entry in self.chara_db.iter() {
+            let id = entry.ui_chara_id;
+            references.want("CharacterDatabaseEntry", id, "ui_series_id", Table::Series, &entry.ui_series_id);
+        }
+        for entry in self.chara_layout_db.iter() {
+            let id = entry.ui_layout_id;
+            references.want("CharacterLayoutDatabaseEntry", id, "ui_chara_id", Table::Chara, &entry.ui_chara_id);
+        }
+        for entry in self.assigned_info.iter() {
+            references.want("AssignedInfoEntry", entry.info_id, "stream_id", Table::StreamProperty, &entry.stream_id);
+        }
+        for entry in self.stream_set.iter() {
+            let infos = [
+                ("info0", &entry.info0),
+                ("info1", &entry.info1),
+                ("info2", &entry.info2),
+                ("info3", &entry.info3),
+                ("info4", &entry.info4),
+                ("info5", &entry.info5),
+                ("info6", &entry.info6),
+                ("info7", &entry.info7),
+                ("info8", &entry.info8),
+                ("info9", &entry.info9),
+                ("info10", &entry.info10),
+                ("info11", &entry.info11),
+                ("info12", &entry.info12),
+                ("info13", &entry.info13),
+                ("info14", &entry.info14),
+                ("info15", &entry.info15),
+            ];
+            for (field, info) in infos {
+                references.want("StreamSetEntry", entry.stream_set_id, field, Table::AssignedInfo, info);
+            }
+        }
+        for entry in self.bgm_db.iter() {
+            let id = entry.ui_bgm_id;
+            references.want("BgmDatabaseRootEntry", id, "stream_set_id", Table::StreamSet, &entry.stream_set_id);
+            references.want("BgmDatabaseRootEntry", id, "ui_gametitle_id", Table::Gametitle, &entry.ui_gametitle_id);
+        }
+        for track in self.playlists.iter().flat_map(|playlist| playlist.tracks.iter()) {
+            let ui_bgm_id = Hash40Type::Overwrite(track.ui_bgm_id);
+            references.want("BgmPlaylistEntry", track.ui_bgm_id, "ui_bgm_id", Table::Bgm, &ui_bgm_id);
+        }
+        for entry in self.stage_db.iter() {
+            let id = entry.ui_stage_id;
+            references.want("StageDatabaseEntry", id, "ui_series_id", Table::Series, &entry.ui_series_id);
+        }
+        for resources in self.ui_stage_resources.iter() {
+            let ui_stage_id = Hash40Type::Overwrite(resources.ui_stage_id);
+            references.want("UiStageData", resources.ui_stage_id, "ui_stage_id", Table::Stage, &ui_stage_id);
+        }
+        references
+    }
+
+    /// Checks the batch and registers all of it, or nothing if there's any issue. Referenced entries
+    /// are registered before the ones referencing them (series before fighters, stream properties before bgm, ...).
+    pub fn commit(self) -> Result<(), BatchError> {
+        let issues = self.check();
+        if !issues.is_empty() {
+            return Err(BatchError { issues });
+        }
+        for entry in self.series_db {
+            crate::add_series_db_entry_info(entry);
+        }
+        for entry in self.gametitle_db.iter() {
+            crate::add_gametitle_db_entry_info(entry);
+        }
+        for entry in self.chara_db {
+            crate::add_chara_db_entry_info(entry);
+        }
+        for entry in self.chara_layout_db {
+            crate::add_chara_layout_db_entry_info(entry);
+        }
+        for ui_chara_id in self.online_charas {
+            crate::allow_ui_chara_hash_online(ui_chara_id);
+        }
+        for entry in self.narration_characall.iter() {
+            crate::add_narration_characall_entry(entry);
+        }
+        for (ui_chara_id, jingle) in self.fighter_jingles.iter() {
+            crate::set_fighter_jingle(*ui_chara_id, jingle);
+        }
+        for entry in self.stream_property.iter() {
+            crate::add_stream_property_entry_info(entry);
+        }
+        for entry in self.assigned_info.iter() {
+            crate::add_assigned_info_entry_info(entry);
+        }
+        for entry in self.stream_set.iter() {
+            crate::add_stream_set_entry_info(entry);
+        }
+        for entry in self.bgm_db.iter() {
+            crate::add_bgm_db_entry_info(entry);
+        }
+        for entry in self.sli.iter() {
+            crate::add_new_sli_entry(entry);
+        }
+        for entry in self.bgm_property.iter() {
+            crate::add_new_bgm_property_entry(entry);
+        }
+        for playlist in self.playlists.iter() {
+            crate::add_tracks_to_playlist(playlist.playlist, &playlist.tracks);
+        }
+        for entry in self.stage_db.iter() {
+            crate::add_stage_db_entry(entry);
+        }
+        for resources in self.ui_stage_resources.iter() {
+            crate::add_ui_stage_db_resources_entry(resources.stage_place_id, resources.ui_stage_id, &resources.data);
+        }
+        for entry in self.tips_db.iter() {
+            crate::add_tips_db_entry_info(entry);
+        }
+        for entry in self.amiibo_db.iter() {
+            crate::add_amiibo_db_entry_info(entry);
+        }
+        for entry in self.mii_body_db.iter() {
+            crate::add_mii_body_db_entry_info(entry);
+        }
+        for entry in self.mii_hat_db.iter() {
+            crate::add_mii_hat_db_entry_info(entry);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&crate::manifest::Manifest> for Batch {
+    fn from(manifest: &crate::manifest::Manifest) -> Self {
+        Batch {
+            series_db: manifest.series_db.clone(),
+            gametitle_db: manifest.gametitle_db.clone(),
+            chara_db: manifest.chara_db.clone(),
+            chara_layout_db: manifest.chara_layout_db.clone(),
+            online_charas: manifest.online_charas.iter().map(|ui_chara_id| ui_chara_id.0).collect(),
+            narration_characall: manifest.narration_characall.clone(),
+            fighter_jingles: manifest.fighter_jingles.iter().map(|jingle| (jingle.ui_chara_id.0, jingle.jingle.clone())).collect(),
+            stream_property: manifest.stream_property.clone(),
+            assigned_info: manifest.assigned_info.clone(),
+            stream_set: manifest.stream_set.clone(),
+            bgm_db: manifest.bgm_db.clone(),
+            sli: manifest.sli.clone(),
+            // Not Clone, but every field is Copy
+            bgm_property: manifest.bgm_property.iter().map(|entry| smash_bgm_property::BgmPropertyEntry { ..*entry }).collect(),
+            playlists: manifest
+                .playlists
+                .iter()
+                .map(|playlist| PlaylistTracks { playlist: playlist.playlist.0, tracks: playlist.tracks.clone() })
+                .collect(),
+            stage_db: manifest.stage_db.clone(),
+            ui_stage_resources: manifest
+                .ui_stage_resources
+                .iter()
+                .map(|resources| UiStageResources {
+                    stage_place_id: resources.stage_place_id.0,
+                    ui_stage_id: resources.ui_stage_id.0,
+                    data: resources.data.clone(),
+                })
+                .collect(),
+            tips_db: manifest.tips_db.clone(),
+            amiibo_db: manifest.amiibo_db.clone(),
+            mii_body_db: manifest.mii_body_db.clone(),
+            mii_hat_db: manifest.mii_hat_db.clone(),
+        }
+    }
+}
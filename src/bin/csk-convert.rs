@@ -0,0 +1,97 @@
+use std::io::Read;
+use std::process::exit;
+
+use the_csk_collection_api::convert::{self, EntryKind};
+use the_csk_collection_api::hash40;
+#[cfg(feature = "serde")]
+use the_csk_collection_api::Hash40;
+
+const USAGE: &str = "Usage: csk-convert [--entry <kind>] [--emit rust|debug|toml|json] [--playlist <id>] [--labels <ParamLabels.csv>] <input.xml | ->";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn main() {
+    let mut kind: Option<EntryKind> = None;
+    let mut emit = String::from("rust");
+    let mut input: Option<String> = None;
+    #[cfg(feature = "serde")]
+    let mut playlist: Option<Hash40> = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--entry" => {
+                let value = args.next().unwrap_or_else(|| fail(USAGE));
+                kind = Some(value.parse().unwrap_or_else(|err: convert::ConvertError| fail(&err.to_string())));
+            }
+            "--emit" => emit = args.next().unwrap_or_else(|| fail(USAGE)),
+            #[cfg(feature = "serde")]
+            "--playlist" => {
+                let value = args.next().unwrap_or_else(|| fail(USAGE));
+                playlist = Some(value.parse().unwrap_or_else(|err: hash40::ParseHash40Error| fail(&err.to_string())));
+            }
+            "--labels" => {
+                let path = args.next().unwrap_or_else(|| fail(USAGE));
+                if let Err(err) = hash40::load_labels(&path) {
+                    fail(&format!("Failed reading labels from {}! Reason: {}", path, err));
+                }
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                let kinds: Vec<&str> = EntryKind::ALL.iter().map(|kind| kind.name()).collect();
+                println!("Entry kinds: {}", kinds.join(", "));
+                return;
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+
+    let xml = match input.as_deref() {
+        Some("-") | None => {
+            let mut xml = String::new();
+            if let Err(err) = std::io::stdin().read_to_string(&mut xml) {
+                fail(&format!("Failed reading stdin! Reason: {}", err));
+            }
+            xml
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .unwrap_or_else(|err| fail(&format!("Failed reading {}! Reason: {}", path, err))),
+    };
+
+    let rows = convert::parse_rows(&xml).unwrap_or_else(|err| fail(&err.to_string()));
+    // So the generated source names the labels the XML was written with
+    for label in rows.iter().flat_map(|row| row.labels.iter()) {
+        hash40::add_label(label);
+    }
+    let kind = match (kind, rows.first()) {
+        (Some(kind), _) => kind,
+        (None, Some(row)) => EntryKind::detect(row)
+            .unwrap_or_else(|| fail("Could not detect the entry type, pass it with --entry!")),
+        (None, None) => fail(&convert::ConvertError::NoRows.to_string()),
+    };
+
+    let output = match emit.as_str() {
+        "rust" => convert::rows_to_rust(kind, &rows).map(|entries| entries.join(",\n")),
+        "debug" => convert::rows_to_debug(kind, &rows).map(|entries| entries.join(",\n")),
+        #[cfg(feature = "serde")]
+        "toml" | "json" => convert::rows_to_manifest(kind, &rows, playlist).map(|manifest| {
+            let serialized = match emit.as_str() {
+                "toml" => manifest.to_toml_string().map_err(|err| err.to_string()),
+                _ => manifest.to_json_string().map_err(|err| err.to_string()),
+            };
+            serialized.unwrap_or_else(|err| fail(&format!("Failed serializing the manifest! Reason: {}", err)))
+        }),
+        _ => fail(USAGE),
+    };
+    match output {
+        Ok(output) => println!("{}", output),
+        Err(convert::ConvertError::MissingPlaylist) => {
+            fail("Playlist rows need the id of the playlist they go in, pass it with --playlist!")
+        }
+        Err(err) => fail(&err.to_string()),
+    }
+}
@@ -0,0 +1,72 @@
+use std::process::exit;
+
+use the_csk_collection_api::manifest::Manifest;
+use the_csk_collection_api::{convert, hash40};
+use the_csk_collection_api::registry::Registry;
+use the_csk_collection_api::validate::{has_errors, Severity, Validate};
+
+const USAGE: &str = "Usage: csk-validate [--deny-warnings] [--labels <ParamLabels.csv>] [--params <ui_*_db.xml>]... \
+                     <manifest.toml | manifest.json>...";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn main() {
+    let mut deny_warnings = false;
+    let mut paths = Vec::new();
+    // Checks the manifests against each other too, as they'd all be installed together
+    let mut registry = Registry::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--deny-warnings" => deny_warnings = true,
+            "--labels" => {
+                let path = args.next().unwrap_or_else(|| fail(USAGE));
+                if let Err(err) = hash40::load_labels(&path) {
+                    fail(&format!("Failed reading labels from {}! Reason: {}", path, err));
+                }
+            }
+            // The game's own params, so manifests reusing its slots are caught
+            "--params" => {
+                let path = args.next().unwrap_or_else(|| fail(USAGE));
+                let xml = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|err| fail(&format!("Failed reading {}! Reason: {}", path, err)));
+                let rows = convert::parse_rows(&xml).unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+                registry.record_params(&rows);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        fail(USAGE);
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        let manifest = Manifest::from_file(path).unwrap_or_else(|err| fail(&err.to_string()));
+        // So the diagnostics name the hashes the way the manifest does
+        manifest.add_labels();
+        let diagnostics = manifest.validate();
+        for diagnostic in diagnostics.iter() {
+            println!("{}: {}", path, diagnostic);
+        }
+        failed |= has_errors(&diagnostics)
+            || (deny_warnings && diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Warning));
+
+        let conflicts = registry.record_manifest(&manifest);
+        for conflict in conflicts.iter() {
+            println!("{}: error: {}", path, conflict);
+        }
+        failed |= !conflicts.is_empty();
+    }
+    if failed {
+        exit(1);
+    }
+}
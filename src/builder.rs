@@ -0,0 +1,88 @@
+//! What the generated entry setters take.
+//!
+//! Every entry gets a setter per field, wrapping the value in `Overwrite`:
+//!
+//! ```ignore
+//! let chara = CharacterDatabaseEntry::clone_from("ui_chara_mario")
+//!     .ui_chara_id("ui_chara_custom")
+//!     .name_id("custom")
+//!     .ui_series_id("ui_series_mario")
+//!     .color_num(8);
+//! ```
+//!
+//! Ids and hash fields take a label (hashed, and added to the label table), a [`Hash40`] or the raw
+//! hash. String fields take `&str` or `String`. Passing the field's own type sets it as is, for
+//! `Optional` values.
+use crate::{CStrCSK, Hash40, Hash40Type, StringType};
+
+pub trait FieldValue<T> {
+    fn into_field(self) -> T;
+}
+
+// The entry's own id
+impl FieldValue<u64> for u64 {
+    fn into_field(self) -> u64 {
+        self
+    }
+}
+
+impl FieldValue<u64> for Hash40 {
+    fn into_field(self) -> u64 {
+        self.0
+    }
+}
+
+impl FieldValue<u64> for &str {
+    fn into_field(self) -> u64 {
+        crate::hash40::add_label(self).0
+    }
+}
+
+// clone_from
+impl FieldValue<Option<u64>> for Option<u64> {
+    fn into_field(self) -> Option<u64> {
+        self
+    }
+}
+
+impl FieldValue<Option<u64>> for u64 {
+    fn into_field(self) -> Option<u64> {
+        Some(self)
+    }
+}
+
+impl FieldValue<Option<u64>> for Hash40 {
+    fn into_field(self) -> Option<u64> {
+        Some(self.0)
+    }
+}
+
+impl FieldValue<Option<u64>> for &str {
+    fn into_field(self) -> Option<u64> {
+        Some(crate::hash40::add_label(self).0)
+    }
+}
+
+impl FieldValue<Hash40Type> for Hash40 {
+    fn into_field(self) -> Hash40Type {
+        Hash40Type::Overwrite(self.0)
+    }
+}
+
+impl FieldValue<Hash40Type> for &str {
+    fn into_field(self) -> Hash40Type {
+        Hash40Type::Overwrite(crate::hash40::add_label(self).0)
+    }
+}
+
+impl FieldValue<StringType> for &str {
+    fn into_field(self) -> StringType {
+        StringType::Overwrite(CStrCSK::new(self))
+    }
+}
+
+impl FieldValue<StringType> for String {
+    fn into_field(self) -> StringType {
+        StringType::Overwrite(CStrCSK::from(self))
+    }
+}
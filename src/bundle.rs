@@ -0,0 +1,53 @@
+//! Bundles build every entry one piece of content needs from a single name, so the ids that have to
+//! match across tables always do, then register them together as a [`Batch`](crate::batch::Batch).
+use std::fmt;
+
+use crate::batch::BatchError;
+
+pub mod fighter;
+pub mod song;
+pub mod stage;
+
+pub use fighter::{FighterBundle, FighterEntries};
+pub use song::{SongBundle, SongEntries};
+pub use stage::{StageBundle, StageEntries, StageForm};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// Names are used to build labels, so they can't be empty or contain NUL bytes.
+    InvalidName(String),
+    /// `color_num` was set but doesn't match the number of layouts given.
+    ColorNumMismatch { color_num: u8, layouts: usize },
+    NoLayouts,
+    /// There's one layout per color and `color_num` is a `u8`, so 255 layouts is the most.
+    TooManyLayouts(usize),
+    /// The entries were built but the batch refused them, so nothing was registered.
+    Batch(BatchError),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::InvalidName(name) => write!(f, "{:?} can't be used as a name!", name),
+            BundleError::ColorNumMismatch { color_num, layouts } => {
+                write!(f, "color_num is {} but {} layouts were given!", color_num, layouts)
+            }
+            BundleError::NoLayouts => write!(f, "No layouts were given!"),
+            BundleError::TooManyLayouts(count) => write!(f, "{} layouts were given, but 255 is the most!", count),
+            BundleError::Batch(err) => write!(f, "Failed registering the bundle! Reason: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+pub(crate) fn check_name(name: &str) -> Result<(), BundleError> {
+    if name.is_empty() || name.contains('\0') {
+        return Err(BundleError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+pub(crate) fn label_hash(label: String) -> u64 {
+    crate::hash40::add_label(&label).0
+}
@@ -0,0 +1,205 @@
+use super::{check_name, label_hash, BundleError};
+use crate::batch::{Batch, BatchError};
+use crate::{
+    CStrCSK, CharacterDatabaseEntry, CharacterLayoutDatabaseEntry, Hash40Type, SeriesDatabaseEntry, StringType,
+    UnsignedByteType,
+};
+
+/// Everything one new fighter needs, derived from its name:
+///
+/// ```ignore
+/// FighterBundle::new("custom")
+///     .clone_from("mario")
+///     .cloned_layouts(8)
+///     .narration()
+///     .jingle("se_jingle_custom")
+///     .allow_online()
+///     .register()?;
+/// ```
+///
+/// registers `ui_chara_custom` cloned from `ui_chara_mario`, with `ui_layout_custom_00` to
+/// `ui_layout_custom_07` cloned from the matching `ui_layout_mario_XX`.
+#[derive(Debug, Clone, Default)]
+pub struct FighterBundle {
+    pub name: String,
+    pub clone_from: Option<String>,
+    // Ids are filled in from the name, everything else is passed along as is
+    pub chara: CharacterDatabaseEntry,
+    // One per color, in color order
+    pub layouts: Vec<CharacterLayoutDatabaseEntry>,
+    pub series: Option<SeriesDatabaseEntry>,
+    pub narration: Option<String>,
+    pub jingle: Option<String>,
+    pub online: bool,
+}
+
+/// The entries a [`FighterBundle`] registers, with every id filled in.
+#[derive(Debug, Clone)]
+pub struct FighterEntries {
+    pub series: Option<SeriesDatabaseEntry>,
+    pub chara: CharacterDatabaseEntry,
+    pub layouts: Vec<CharacterLayoutDatabaseEntry>,
+    pub narration: Option<String>,
+    pub jingle: Option<String>,
+    pub online: bool,
+}
+
+impl FighterBundle {
+    pub fn new(name: &str) -> Self {
+        FighterBundle { name: name.to_string(), ..Default::default() }
+    }
+
+    /// The fighter to copy every unset value from, e.g. `"mario"`.
+    pub fn clone_from(mut self, name: &str) -> Self {
+        self.clone_from = Some(name.to_string());
+        self
+    }
+
+    pub fn chara(mut self, chara: CharacterDatabaseEntry) -> Self {
+        self.chara = chara;
+        self
+    }
+
+    /// Adds the layout for the next color.
+    pub fn layout(mut self, layout: CharacterLayoutDatabaseEntry) -> Self {
+        self.layouts.push(layout);
+        self
+    }
+
+    /// Adds `count` layouts that copy everything from the cloned fighter's layouts.
+    pub fn cloned_layouts(mut self, count: usize) -> Self {
+        self.layouts.extend((0..count).map(|_| CharacterLayoutDatabaseEntry::default()));
+        self
+    }
+
+    pub fn series(mut self, series: SeriesDatabaseEntry) -> Self {
+        self.series = Some(series);
+        self
+    }
+
+    /// Adds the announcer call `vc_narration_characall_<name>`.
+    pub fn narration(self) -> Self {
+        let label = format!("vc_narration_characall_{}", self.name);
+        self.narration_label(&label)
+    }
+
+    pub fn narration_label(mut self, label: &str) -> Self {
+        self.narration = Some(label.to_string());
+        self
+    }
+
+    pub fn jingle(mut self, jingle: &str) -> Self {
+        self.jingle = Some(jingle.to_string());
+        self
+    }
+
+    pub fn allow_online(mut self) -> Self {
+        self.online = true;
+        self
+    }
+
+    pub fn ui_chara_id(&self) -> u64 {
+        label_hash(format!("ui_chara_{}", self.name))
+    }
+
+    pub fn ui_layout_id(&self, color: usize) -> u64 {
+        label_hash(format!("ui_layout_{}_{:02}", self.name, color))
+    }
+
+    /// Fills in the ids and checks everything fits together, without registering anything.
+    pub fn entries(&self) -> Result<FighterEntries, BundleError> {
+        check_name(&self.name)?;
+        if let Some(clone_from) = self.clone_from.as_ref() {
+            check_name(clone_from)?;
+        }
+        for label in self.narration.iter().chain(self.jingle.iter()) {
+            check_name(label)?;
+        }
+        if self.layouts.is_empty() {
+            return Err(BundleError::NoLayouts);
+        }
+        let color_num =
+            u8::try_from(self.layouts.len()).map_err(|_| BundleError::TooManyLayouts(self.layouts.len()))?;
+        match self.chara.color_num {
+            UnsignedByteType::Overwrite(set) | UnsignedByteType::Optional(Some(set)) if set != color_num => {
+                return Err(BundleError::ColorNumMismatch { color_num: set, layouts: self.layouts.len() });
+            }
+            _ => {}
+        }
+
+        let ui_chara_id = self.ui_chara_id();
+        let series = self.series.clone();
+
+        let mut chara = self.chara.clone();
+        chara.ui_chara_id = ui_chara_id;
+        if chara.clone_from_ui_chara_id.is_none() {
+            chara.clone_from_ui_chara_id =
+                self.clone_from.as_ref().map(|clone_from| label_hash(format!("ui_chara_{}", clone_from)));
+        }
+        if matches!(chara.name_id, StringType::Optional(None)) {
+            chara.name_id = StringType::Overwrite(CStrCSK::new(&self.name));
+        }
+        if let Some(series) = series.as_ref() {
+            chara.ui_series_id = Hash40Type::Overwrite(series.ui_series_id);
+        }
+        chara.color_num = UnsignedByteType::Overwrite(color_num);
+
+        let layouts = self
+            .layouts
+            .iter()
+            .enumerate()
+            .map(|(color, layout)| {
+                let mut layout = layout.clone();
+                layout.ui_layout_id = self.ui_layout_id(color);
+                if layout.clone_from_ui_layout_id.is_none() {
+                    layout.clone_from_ui_layout_id = self
+                        .clone_from
+                        .as_ref()
+                        .map(|clone_from| label_hash(format!("ui_layout_{}_{:02}", clone_from, color)));
+                }
+                layout.ui_chara_id = Hash40Type::Overwrite(ui_chara_id);
+                layout.chara_color = UnsignedByteType::Overwrite(color as u8);
+                layout
+            })
+            .collect();
+
+        Ok(FighterEntries {
+            series,
+            chara,
+            layouts,
+            narration: self.narration.clone(),
+            jingle: self.jingle.clone(),
+            online: self.online,
+        })
+    }
+
+    /// Checks the bundle, then registers all of it. Nothing is registered if the check fails.
+    pub fn register(&self) -> Result<FighterEntries, BundleError> {
+        let entries = self.entries()?;
+        entries.register().map_err(BundleError::Batch)?;
+        Ok(entries)
+    }
+}
+
+impl FighterEntries {
+    /// Registers the entries as a [`Batch`], which puts the series before the fighter and the fighter before
+    /// its layouts and the rest keyed by `ui_chara_id`.
+    pub fn register(&self) -> Result<(), BatchError> {
+        Batch::from(self).commit()
+    }
+}
+
+impl From<&FighterEntries> for Batch {
+    fn from(entries: &FighterEntries) -> Self {
+        let ui_chara_id = entries.chara.ui_chara_id;
+        Batch {
+            series_db: entries.series.iter().cloned().collect(),
+            chara_db: vec![entries.chara.clone()],
+            chara_layout_db: entries.layouts.clone(),
+            online_charas: if entries.online { vec![ui_chara_id] } else { Vec::new() },
+            narration_characall: entries.narration.iter().cloned().collect(),
+            fighter_jingles: entries.jingle.iter().map(|jingle| (ui_chara_id, jingle.clone())).collect(),
+            ..Default::default()
+        }
+    }
+}
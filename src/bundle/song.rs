@@ -0,0 +1,224 @@
+use super::{check_name, label_hash, BundleError};
+use crate::batch::{Batch, BatchError, PlaylistTracks};
+use crate::{
+    AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CStrCSK, Hash40Type, StreamPropertyEntry,
+    StreamSetEntry, StringType,
+};
+
+/// One new track, with the whole lookup chain derived from its name:
+///
+/// `ui_bgm_<name>` → `set_<name>` → `info_<name>` → `stream_<name>` → data `<name>`,
+/// with the SLI and bgm property entries keyed by `bgm_<name>`.
+///
+/// ```ignore
+/// SongBundle::new("custom_song")
+///     .clone_from("a01_smb_chijyou")
+///     .sli(0x1AB, 0)
+///     .playlist("bgmmario")
+///     .register()?;
+/// ```
+#[derive(Debug, Default)]
+pub struct SongBundle {
+    pub name: String,
+    pub clone_from: Option<String>,
+    // The linking ids are filled in from the name, everything else is passed along as is
+    pub bgm: BgmDatabaseRootEntry,
+    pub stream_set: StreamSetEntry,
+    pub assigned_info: AssignedInfoEntry,
+    pub stream_property: StreamPropertyEntry,
+    // (nus3bank_id, tone_id)
+    pub sli: Option<(u32, u32)>,
+    pub bgm_property: Option<smash_bgm_property::BgmPropertyEntry>,
+    pub playlists: Vec<(u64, BgmPlaylistEntry)>,
+}
+
+/// The entries a [`SongBundle`] registers, with every id filled in.
+#[derive(Debug)]
+pub struct SongEntries {
+    pub stream_property: StreamPropertyEntry,
+    pub assigned_info: AssignedInfoEntry,
+    pub stream_set: StreamSetEntry,
+    pub bgm: BgmDatabaseRootEntry,
+    pub sli: Option<smash_sli::SliEntry>,
+    pub bgm_property: Option<smash_bgm_property::BgmPropertyEntry>,
+    pub playlists: Vec<(u64, BgmPlaylistEntry)>,
+}
+
+impl SongBundle {
+    pub fn new(name: &str) -> Self {
+        SongBundle { name: name.to_string(), ..Default::default() }
+    }
+
+    /// The track to copy every unset value from, by its base name (`"a01_smb_chijyou"`).
+    pub fn clone_from(mut self, name: &str) -> Self {
+        self.clone_from = Some(name.to_string());
+        self
+    }
+
+    pub fn bgm(mut self, bgm: BgmDatabaseRootEntry) -> Self {
+        self.bgm = bgm;
+        self
+    }
+
+    pub fn stream_set(mut self, stream_set: StreamSetEntry) -> Self {
+        self.stream_set = stream_set;
+        self
+    }
+
+    pub fn assigned_info(mut self, assigned_info: AssignedInfoEntry) -> Self {
+        self.assigned_info = assigned_info;
+        self
+    }
+
+    pub fn stream_property(mut self, stream_property: StreamPropertyEntry) -> Self {
+        self.stream_property = stream_property;
+        self
+    }
+
+    /// Where the track's sound lives: the id of its nus3bank and its tone id in there.
+    pub fn sli(mut self, nus3bank_id: u32, tone_id: u32) -> Self {
+        self.sli = Some((nus3bank_id, tone_id));
+        self
+    }
+
+    /// Loop points and duration. `stream_name` is filled in.
+    pub fn bgm_property(mut self, bgm_property: smash_bgm_property::BgmPropertyEntry) -> Self {
+        self.bgm_property = Some(bgm_property);
+        self
+    }
+
+    /// Loop points and duration read from the track's `.lopus` or `.idsp`.
+    pub fn bgm_property_from_audio(self, data: &[u8]) -> Result<Self, crate::audio_header::AudioHeaderError> {
+        let info = crate::audio_header::AudioInfo::parse(data)?;
+        let bgm_property = info.bgm_property(&format!("bgm_{}", self.name));
+        Ok(self.bgm_property(bgm_property))
+    }
+
+    /// Adds the track to a playlist (e.g. `"bgmmario"`) with the default order and incidence.
+    pub fn playlist(self, playlist: &str) -> Self {
+        self.playlist_entry(playlist, BgmPlaylistEntry::default())
+    }
+
+    /// Adds the track to a playlist with its own order and incidence. `ui_bgm_id` is filled in.
+    pub fn playlist_entry(mut self, playlist: &str, entry: BgmPlaylistEntry) -> Self {
+        self.playlists.push((label_hash(playlist.to_string()), entry));
+        self
+    }
+
+    pub fn ui_bgm_id(&self) -> u64 {
+        label_hash(format!("ui_bgm_{}", self.name))
+    }
+
+    pub fn stream_set_id(&self) -> u64 {
+        label_hash(format!("set_{}", self.name))
+    }
+
+    pub fn info_id(&self) -> u64 {
+        label_hash(format!("info_{}", self.name))
+    }
+
+    pub fn stream_id(&self) -> u64 {
+        label_hash(format!("stream_{}", self.name))
+    }
+
+    /// The name the SLI and bgm property entries are keyed by.
+    pub fn tone_name(&self) -> u64 {
+        label_hash(format!("bgm_{}", self.name))
+    }
+
+    // The id `prefix` would give the cloned track
+    fn clone_id(&self, prefix: &str) -> Option<u64> {
+        self.clone_from.as_ref().map(|clone_from| label_hash(format!("{}{}", prefix, clone_from)))
+    }
+
+    /// Fills in the ids and checks the names, without registering anything.
+    pub fn entries(&self) -> Result<SongEntries, BundleError> {
+        check_name(&self.name)?;
+        if let Some(clone_from) = self.clone_from.as_ref() {
+            check_name(clone_from)?;
+        }
+
+        let mut stream_property = self.stream_property.clone();
+        stream_property.stream_id = self.stream_id();
+        if stream_property.clone_from_stream_id.is_none() {
+            stream_property.clone_from_stream_id = self.clone_id("stream_");
+        }
+        if matches!(stream_property.data_name0, StringType::Optional(None)) {
+            stream_property.data_name0 = StringType::Overwrite(CStrCSK::new(&self.name));
+        }
+
+        let mut assigned_info = self.assigned_info.clone();
+        assigned_info.info_id = self.info_id();
+        if assigned_info.clone_from_info_id.is_none() {
+            assigned_info.clone_from_info_id = self.clone_id("info_");
+        }
+        assigned_info.stream_id = Hash40Type::Overwrite(stream_property.stream_id);
+
+        let mut stream_set = self.stream_set.clone();
+        stream_set.stream_set_id = self.stream_set_id();
+        if stream_set.clone_from_stream_set_id.is_none() {
+            stream_set.clone_from_stream_set_id = self.clone_id("set_");
+        }
+        stream_set.info0 = Hash40Type::Overwrite(assigned_info.info_id);
+
+        let mut bgm = self.bgm.clone();
+        bgm.ui_bgm_id = self.ui_bgm_id();
+        if bgm.clone_from_ui_bgm_id.is_none() {
+            bgm.clone_from_ui_bgm_id = self.clone_id("ui_bgm_");
+        }
+        bgm.stream_set_id = Hash40Type::Overwrite(stream_set.stream_set_id);
+        if matches!(bgm.name_id, StringType::Optional(None)) {
+            bgm.name_id = StringType::Overwrite(CStrCSK::new(&self.name));
+        }
+
+        let tone_name = smash_sli::Hash40(self.tone_name());
+        let sli = self.sli.map(|(nus3bank_id, tone_id)| smash_sli::SliEntry { tone_name, nus3bank_id, tone_id });
+        let bgm_property = self
+            .bgm_property
+            .as_ref()
+            .map(|property| smash_bgm_property::BgmPropertyEntry { stream_name: tone_name, ..*property });
+
+        let playlists = self
+            .playlists
+            .iter()
+            .map(|(playlist, entry)| (*playlist, BgmPlaylistEntry { ui_bgm_id: bgm.ui_bgm_id, ..entry.clone() }))
+            .collect();
+
+        Ok(SongEntries { stream_property, assigned_info, stream_set, bgm, sli, bgm_property, playlists })
+    }
+
+    /// Checks the bundle, then registers all of it. Nothing is registered if the check fails.
+    pub fn register(&self) -> Result<SongEntries, BundleError> {
+        let entries = self.entries()?;
+        entries.register().map_err(BundleError::Batch)?;
+        Ok(entries)
+    }
+}
+
+impl SongEntries {
+    /// Registers the entries as a [`Batch`], which registers the chain from the stream up so every id exists
+    /// before something refers to it.
+    pub fn register(&self) -> Result<(), BatchError> {
+        Batch::from(self).commit()
+    }
+}
+
+impl From<&SongEntries> for Batch {
+    fn from(entries: &SongEntries) -> Self {
+        Batch {
+            stream_property: vec![entries.stream_property.clone()],
+            assigned_info: vec![entries.assigned_info.clone()],
+            stream_set: vec![entries.stream_set.clone()],
+            bgm_db: vec![entries.bgm.clone()],
+            sli: entries.sli.iter().cloned().collect(),
+            // Not Clone, but every field is Copy
+            bgm_property: entries.bgm_property.iter().map(|entry| smash_bgm_property::BgmPropertyEntry { ..*entry }).collect(),
+            playlists: entries
+                .playlists
+                .iter()
+                .map(|(playlist, entry)| PlaylistTracks { playlist: *playlist, tracks: vec![entry.clone()] })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::{check_name, label_hash, BundleError};
+use crate::batch::{self, Batch, BatchError, PlaylistTracks};
+use crate::{BgmPlaylistEntry, CStrCSK, Hash40Type, StageDatabaseEntry, StringType, UiStageData, UiStageResources};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StageForm {
+    Normal,
+    Battlefield,
+    Omega,
+}
+
+impl StageForm {
+    pub const ALL: [StageForm; 3] = [StageForm::Normal, StageForm::Battlefield, StageForm::Omega];
+
+    /// The folder the form's files live in under `stage/<name>/`.
+    pub fn dir(&self) -> &'static str {
+        match self {
+            StageForm::Normal => "normal",
+            StageForm::Battlefield => "battle",
+            StageForm::Omega => "end",
+        }
+    }
+
+    pub fn path(&self, name: &str) -> String {
+        format!("stage/{}/{}", name, self.dir())
+    }
+
+    /// The standard resources for the form of the stage `name`: its own load group, with the effects
+    /// and sound files every form of the stage shares.
+    pub fn resources(&self, name: &str) -> UiStageResources {
+        let sound = |ext: &str| label_hash(format!("sound/bank/stage/se_stage_{}.{}", name, ext));
+        UiStageResources {
+            stage_load_group_hash: label_hash(self.path(name)),
+            effect_load_group_hash: label_hash(format!("effect/stage/{}", name)),
+            nus3bank_path_hash: sound("nus3bank"),
+            sqb_path_hash: sound("sqb"),
+            nus3audio_path_hash: sound("nus3audio"),
+            tonelabel_path_hash: sound("tonelabel"),
+        }
+    }
+}
+
+/// A new stage, with its `ui_stage_db` entry and resources agreeing on the ids:
+///
+/// ```ignore
+/// StageBundle::new("custom_stage")
+///     .clone_from("battlefield")
+///     .new_playlist("bgmcustom_stage", tracks)
+///     .register()?;
+/// ```
+///
+/// registers `ui_stage_custom_stage` placed at `custom_stage`, loading `stage/custom_stage/{normal,battle,end}`.
+#[derive(Debug, Clone, Default)]
+pub struct StageBundle {
+    pub name: String,
+    pub clone_from: Option<String>,
+    // The ids are filled in from the name, everything else is passed along as is
+    pub stage: StageDatabaseEntry,
+    // Replacements for the derived resources, by form
+    pub resources: [Option<UiStageResources>; 3],
+    pub playlist: Option<u64>,
+    // Tracks for a new playlist
+    pub tracks: Vec<BgmPlaylistEntry>,
+}
+
+/// The entries a [`StageBundle`] registers, with every id filled in.
+#[derive(Debug, Clone)]
+pub struct StageEntries {
+    pub stage: StageDatabaseEntry,
+    pub stage_place_id: u64,
+    pub resources: UiStageData,
+    pub playlist: Option<(u64, Vec<BgmPlaylistEntry>)>,
+}
+
+impl StageBundle {
+    pub fn new(name: &str) -> Self {
+        StageBundle { name: name.to_string(), ..Default::default() }
+    }
+
+    /// The stage to copy every unset value from, by its place name (`"battlefield"`).
+    pub fn clone_from(mut self, name: &str) -> Self {
+        self.clone_from = Some(name.to_string());
+        self
+    }
+
+    pub fn stage(mut self, stage: StageDatabaseEntry) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// Uses `resources` for `form` instead of the derived ones.
+    pub fn resources(mut self, form: StageForm, resources: UiStageResources) -> Self {
+        self.resources[form as usize] = Some(resources);
+        self
+    }
+
+    /// Plays an existing playlist (e.g. `"bgmmario"`) on the stage.
+    pub fn playlist(mut self, playlist: &str) -> Self {
+        self.playlist = Some(label_hash(playlist.to_string()));
+        self
+    }
+
+    /// Plays a new playlist made of `tracks` on the stage.
+    pub fn new_playlist(mut self, playlist: &str, tracks: Vec<BgmPlaylistEntry>) -> Self {
+        self.tracks = tracks;
+        self.playlist(playlist)
+    }
+
+    pub fn ui_stage_id(&self) -> u64 {
+        label_hash(format!("ui_stage_{}", self.name))
+    }
+
+    pub fn stage_place_id(&self) -> u64 {
+        label_hash(self.name.clone())
+    }
+
+    /// Fills in the ids and checks the names, without registering anything.
+    pub fn entries(&self) -> Result<StageEntries, BundleError> {
+        check_name(&self.name)?;
+        if let Some(clone_from) = self.clone_from.as_ref() {
+            check_name(clone_from)?;
+        }
+
+        let stage_place_id = self.stage_place_id();
+        let mut stage = self.stage.clone();
+        stage.ui_stage_id = self.ui_stage_id();
+        if stage.clone_from_ui_stage_id.is_none() {
+            stage.clone_from_ui_stage_id =
+                self.clone_from.as_ref().map(|clone_from| label_hash(format!("ui_stage_{}", clone_from)));
+        }
+        if matches!(stage.name_id, StringType::Optional(None)) {
+            stage.name_id = StringType::Overwrite(CStrCSK::new(&self.name));
+        }
+        stage.stage_place_id = Hash40Type::Overwrite(stage_place_id);
+        if let Some(playlist) = self.playlist {
+            stage.bgm_set_id = Hash40Type::Overwrite(playlist);
+        }
+
+        let [normal, battle, end] =
+            StageForm::ALL.map(|form| self.resources[form as usize].clone().unwrap_or_else(|| form.resources(&self.name)));
+        let resources = UiStageData { normal, end, battle };
+
+        let playlist = match self.playlist {
+            Some(playlist) if !self.tracks.is_empty() => Some((playlist, self.tracks.clone())),
+            _ => None,
+        };
+
+        Ok(StageEntries { stage, stage_place_id, resources, playlist })
+    }
+
+    /// Checks the bundle, then registers all of it. Nothing is registered if the check fails.
+    pub fn register(&self) -> Result<StageEntries, BundleError> {
+        let entries = self.entries()?;
+        entries.register().map_err(BundleError::Batch)?;
+        Ok(entries)
+    }
+}
+
+impl StageEntries {
+    /// Registers the entries as a [`Batch`], which puts the playlist first so `bgm_set_id` points at something,
+    /// then the stage and its resources.
+    pub fn register(&self) -> Result<(), BatchError> {
+        Batch::from(self).commit()
+    }
+}
+
+impl From<&StageEntries> for Batch {
+    fn from(entries: &StageEntries) -> Self {
+        Batch {
+            playlists: entries
+                .playlist
+                .iter()
+                .map(|(playlist, tracks)| PlaylistTracks { playlist: *playlist, tracks: tracks.clone() })
+                .collect(),
+            stage_db: vec![entries.stage.clone()],
+            ui_stage_resources: vec![batch::UiStageResources {
+                stage_place_id: entries.stage_place_id,
+                ui_stage_id: entries.stage.ui_stage_id,
+                data: entries.resources.clone(),
+            }],
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Typed access to the per-color keys of [`CharacterDatabaseEntry`].
+//!
+//! `ui_chara_db` has a `c00_index`, `n00_index`, `c00_group`, `characall_label_c00` and
+//! `characall_label_article_c00` for every color slot. They're kept by hash in `extra_index_maps`
+//! and `extra_hash_maps`, and the functions here work out the right key for a slot.
+//!
+//! A slot is a `u8` like `color_num`, so every slot has keys: `c00` to `c99`, then `c100` to `c255`. The setters
+//! only take slots the entry has colors for, which is all of `c00` to `c99` when it leaves `color_num` unset.
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{CharacterDatabaseEntry, Hash40Map, Hash40Type, UnsignedByteMap, UnsignedByteType};
+
+/// The slots the setters take when an entry leaves `color_num` unset, `c00` to `c99`.
+pub const MAX_COLOR_SLOTS: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotOutOfRange {
+    pub slot: u8,
+    /// The entry's `color_num`, or [`MAX_COLOR_SLOTS`] if it doesn't set one.
+    pub slots: u8,
+}
+
+impl fmt::Display for SlotOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Color slot {} is out of range, the entry only has {} color slots!", self.slot, self.slots)
+    }
+}
+
+impl std::error::Error for SlotOutOfRange {}
+
+/// The keys in `extra_index_maps`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexKey {
+    /// `cXX_index`, the color of the fighter's files shown in the slot.
+    ColorIndex,
+    /// `nXX_index`, which name and announcer call the slot uses.
+    NarrationIndex,
+    /// `cXX_group`, the group the slot's color is in.
+    ColorGroup,
+}
+
+/// The keys in `extra_hash_maps`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LabelKey {
+    /// `characall_label_cXX`, the announcer call for the slot.
+    CharacallLabel,
+    /// `characall_label_article_cXX`, the call with an article, for languages that have one.
+    CharacallLabelArticle,
+}
+
+impl IndexKey {
+    pub const ALL: [IndexKey; 3] = [IndexKey::ColorIndex, IndexKey::NarrationIndex, IndexKey::ColorGroup];
+
+    pub fn label(&self, slot: u8) -> String {
+        match self {
+            IndexKey::ColorIndex => format!("c{:02}_index", slot),
+            IndexKey::NarrationIndex => format!("n{:02}_index", slot),
+            IndexKey::ColorGroup => format!("c{:02}_group", slot),
+        }
+    }
+
+    pub fn key(&self, slot: u8) -> u64 {
+        crate::hash40(&self.label(slot)).0
+    }
+}
+
+impl LabelKey {
+    pub const ALL: [LabelKey; 2] = [LabelKey::CharacallLabel, LabelKey::CharacallLabelArticle];
+
+    pub fn label(&self, slot: u8) -> String {
+        match self {
+            LabelKey::CharacallLabel => format!("characall_label_c{:02}", slot),
+            LabelKey::CharacallLabelArticle => format!("characall_label_article_c{:02}", slot),
+        }
+    }
+
+    pub fn key(&self, slot: u8) -> u64 {
+        crate::hash40(&self.label(slot)).0
+    }
+}
+
+impl CharacterDatabaseEntry {
+    fn check_slot(&self, slot: u8) -> Result<(), SlotOutOfRange> {
+        let slots = self.color_num.value().copied().unwrap_or(MAX_COLOR_SLOTS);
+        if slot >= slots {
+            return Err(SlotOutOfRange { slot, slots });
+        }
+        Ok(())
+    }
+
+    pub fn set_index(&mut self, key: IndexKey, slot: u8, value: u8) -> Result<(), SlotOutOfRange> {
+        self.check_slot(slot)?;
+        let key = key.key(slot);
+        if let UnsignedByteMap::Optional(None) = self.extra_index_maps {
+            self.extra_index_maps = UnsignedByteMap::Overwrite(HashMap::new());
+        }
+        if let UnsignedByteMap::Overwrite(map) | UnsignedByteMap::Optional(Some(map)) = &mut self.extra_index_maps {
+            map.insert(key, UnsignedByteType::Overwrite(value));
+        }
+        Ok(())
+    }
+
+    pub fn set_label(&mut self, key: LabelKey, slot: u8, label: u64) -> Result<(), SlotOutOfRange> {
+        self.check_slot(slot)?;
+        let key = key.key(slot);
+        if let Hash40Map::Optional(None) = self.extra_hash_maps {
+            self.extra_hash_maps = Hash40Map::Overwrite(HashMap::new());
+        }
+        if let Hash40Map::Overwrite(map) | Hash40Map::Optional(Some(map)) = &mut self.extra_hash_maps {
+            map.insert(key, Hash40Type::Overwrite(label));
+        }
+        Ok(())
+    }
+
+    pub fn get_index(&self, key: IndexKey, slot: u8) -> Option<u8> {
+        let key = key.key(slot);
+        self.extra_index_maps.value()?.get(&key)?.value().copied()
+    }
+
+    pub fn get_label(&self, key: LabelKey, slot: u8) -> Option<u64> {
+        let key = key.key(slot);
+        self.extra_hash_maps.value()?.get(&key)?.value().copied()
+    }
+
+    pub fn set_color_index(&mut self, slot: u8, index: u8) -> Result<(), SlotOutOfRange> {
+        self.set_index(IndexKey::ColorIndex, slot, index)
+    }
+
+    pub fn set_narration_index(&mut self, slot: u8, index: u8) -> Result<(), SlotOutOfRange> {
+        self.set_index(IndexKey::NarrationIndex, slot, index)
+    }
+
+    pub fn set_color_group(&mut self, slot: u8, group: u8) -> Result<(), SlotOutOfRange> {
+        self.set_index(IndexKey::ColorGroup, slot, group)
+    }
+
+    pub fn set_characall_label(&mut self, slot: u8, label: u64) -> Result<(), SlotOutOfRange> {
+        self.set_label(LabelKey::CharacallLabel, slot, label)
+    }
+
+    pub fn set_characall_label_article(&mut self, slot: u8, label: u64) -> Result<(), SlotOutOfRange> {
+        self.set_label(LabelKey::CharacallLabelArticle, slot, label)
+    }
+
+    pub fn color_index(&self, slot: u8) -> Option<u8> {
+        self.get_index(IndexKey::ColorIndex, slot)
+    }
+
+    pub fn narration_index(&self, slot: u8) -> Option<u8> {
+        self.get_index(IndexKey::NarrationIndex, slot)
+    }
+
+    pub fn color_group(&self, slot: u8) -> Option<u8> {
+        self.get_index(IndexKey::ColorGroup, slot)
+    }
+
+    pub fn characall_label(&self, slot: u8) -> Option<u64> {
+        self.get_label(LabelKey::CharacallLabel, slot)
+    }
+
+    pub fn characall_label_article(&self, slot: u8) -> Option<u64> {
+        self.get_label(LabelKey::CharacallLabelArticle, slot)
+    }
+}
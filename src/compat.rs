@@ -0,0 +1,165 @@
+//! Checks against the installed CSK Collection version.
+//!
+//! Every `add_*` function has a `try_*` twin here that first makes sure the plugin is installed and,
+//! where the release that added the function is known, new enough to have it, returning a
+//! [`CompatError`] instead of calling a symbol that isn't there.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, MiiBodyDatabaseEntry, MiiHatDatabaseEntry,
+    SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, TipsDatabaseEntry, UiStageData,
+    Version,
+};
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version { major, minor, patch }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVersionError(pub String);
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a version! Expected major.minor.patch", self.0)
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+// Takes "1.2.3", "v1.2.3" or "1.2" (patch 0)
+impl FromStr for Version {
+    type Err = ParseVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseVersionError(s.to_string());
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_prefix(['v', 'V']).unwrap_or(trimmed);
+        let parts = trimmed.split('.').map(|part| part.parse::<u32>().map_err(|_| err())).collect::<Result<Vec<_>, _>>()?;
+        match parts[..] {
+            [major, minor] => Ok(Version::new(major, minor, 0)),
+            [major, minor, patch] => Ok(Version::new(major, minor, patch)),
+            _ => Err(err()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompatError {
+    /// The plugin didn't report a version, most likely because it isn't installed.
+    PluginMissing,
+    Unsupported { api: Api, needed: Version, found: Version },
+}
+
+impl fmt::Display for CompatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatError::PluginMissing => write!(f, "Failed getting the CSK Collection version! Is the plugin installed?"),
+            CompatError::Unsupported { api, needed, found } => write!(
+                f,
+                "{} needs CSK Collection {} or newer, but {} is installed!",
+                api.name(),
+                needed,
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompatError {}
+
+macro_rules! since {
+    () => {
+        None
+    };
+    ($major:literal, $minor:literal, $patch:literal) => {
+        Some(Version::new($major, $minor, $patch))
+    };
+}
+
+// Builds the API table and the try_* functions from one list:
+// `Api variant: function / try_function (args) -> return type, since (major, minor, patch);`
+// with the `since` left out while the release that added the function isn't known
+macro_rules! compat_table {
+    ($($api:ident: $name:ident / $try_name:ident ($($arg:ident: $arg_type:ty),*) -> $ret:ty $(, since ($($since:literal),*))?;)*) => {
+        /// Every plugin function that may be missing from older CSK Collection releases.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Api {
+            $($api,)*
+        }
+
+        impl Api {
+            pub const ALL: &'static [Api] = &[$(Api::$api,)*];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Api::$api => stringify!($name),)*
+                }
+            }
+
+            /// The first CSK Collection version exporting this function, or `None` if that isn't known
+            /// and any installed plugin is taken to have it.
+            pub fn min_version(&self) -> Option<Version> {
+                match self {
+                    $(Api::$api => since!($($($since),*)?),)*
+                }
+            }
+        }
+
+        $(
+            #[doc = concat!("[`crate::", stringify!($name), "`], after checking the plugin has it.")]
+            // Most of these wrap functions returning ()
+            #[allow(clippy::unit_arg)]
+            pub fn $try_name($($arg: $arg_type),*) -> Result<$ret, CompatError> {
+                check(Api::$api)?;
+                Ok(crate::$name($($arg),*))
+            }
+        )*
+    };
+}
+
+compat_table! {
+    // Only the newer tables have a `since`, any installed plugin is taken to have the rest
+    CharaDb: add_chara_db_entry_info / try_add_chara_db_entry_info (entry: CharacterDatabaseEntry) -> ();
+    CharaLayoutDb: add_chara_layout_db_entry_info / try_add_chara_layout_db_entry_info (entry: CharacterLayoutDatabaseEntry) -> ();
+    SeriesDb: add_series_db_entry_info / try_add_series_db_entry_info (entry: SeriesDatabaseEntry) -> ();
+    NarrationCharacall: add_narration_characall_entry / try_add_narration_characall_entry (entry: &str) -> bool;
+    BgmDb: add_bgm_db_entry_info / try_add_bgm_db_entry_info (entry: &BgmDatabaseRootEntry) -> ();
+    StreamSet: add_stream_set_entry_info / try_add_stream_set_entry_info (entry: &StreamSetEntry) -> ();
+    AssignedInfo: add_assigned_info_entry_info / try_add_assigned_info_entry_info (entry: &AssignedInfoEntry) -> ();
+    StreamProperty: add_stream_property_entry_info / try_add_stream_property_entry_info (entry: &StreamPropertyEntry) -> ();
+    Sli: add_new_sli_entry / try_add_new_sli_entry (entry: &smash_sli::SliEntry) -> ();
+    BgmProperty: add_new_bgm_property_entry / try_add_new_bgm_property_entry (entry: &smash_bgm_property::BgmPropertyEntry) -> ();
+    Playlist: add_tracks_to_playlist / try_add_tracks_to_playlist (playlist: u64, tracks: &Vec<BgmPlaylistEntry>) -> ();
+    StageDb: add_stage_db_entry / try_add_stage_db_entry (entry: &StageDatabaseEntry) -> ();
+    UiStageResources: add_ui_stage_db_resources_entry / try_add_ui_stage_db_resources_entry (stage_place_id: u64, ui_stage_id: u64, stage_data: &UiStageData) -> (), since (1, 3, 0);
+    GametitleDb: add_gametitle_db_entry_info / try_add_gametitle_db_entry_info (entry: &GametitleDatabaseEntry) -> (), since (1, 4, 0);
+    TipsDb: add_tips_db_entry_info / try_add_tips_db_entry_info (entry: &TipsDatabaseEntry) -> (), since (1, 4, 0);
+    AmiiboDb: add_amiibo_db_entry_info / try_add_amiibo_db_entry_info (entry: &AmiiboDatabaseEntry) -> (), since (1, 4, 0);
+    MiiBodyDb: add_mii_body_db_entry_info / try_add_mii_body_db_entry_info (entry: &MiiBodyDatabaseEntry) -> (), since (1, 4, 0);
+    MiiHatDb: add_mii_hat_db_entry_info / try_add_mii_hat_db_entry_info (entry: &MiiHatDatabaseEntry) -> (), since (1, 4, 0);
+}
+
+impl fmt::Display for Api {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Whether the installed plugin has `api`.
+pub fn check(api: Api) -> Result<(), CompatError> {
+    let found = crate::get_plugin_version().ok_or(CompatError::PluginMissing)?;
+    match api.min_version() {
+        Some(needed) if found < needed => Err(CompatError::Unsupported { api, needed, found }),
+        _ => Ok(()),
+    }
+}
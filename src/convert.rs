@@ -0,0 +1,739 @@
+//! Converts paramxml `<struct>` rows (as exported from the game's `.prc` files) into entry structs or Rust source.
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{
+    hash40, AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, BoolType,
+    CStrCSK, CharacterDatabaseEntry, CharacterLayoutDatabaseEntry, FloatType, GametitleDatabaseEntry,
+    Hash40, Hash40Map, Hash40Type, IntType, MiiBodyDatabaseEntry, MiiHatDatabaseEntry, SeriesDatabaseEntry,
+    ShortType, SignedByteType, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, StringType,
+    TipsDatabaseEntry, UnsignedByteMap, UnsignedByteType, UnsignedIntType, UnsignedShortType,
+};
+
+const CRATE_PREFIX: &str = "the_csk_collection_api";
+
+// Keys that get written as Optional(Some(..)) instead of Overwrite(..)
+const OPTIONAL_KEYS: &[&str] = &["disp_order", "disp_order_sound"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    Xml { line: usize, message: String },
+    UnknownType { line: usize, node: String },
+    InvalidValue { field: String, value: String },
+    UnknownEntryKind(String),
+    NoRows,
+    /// Playlist rows were converted without saying which playlist they go in.
+    MissingPlaylist,
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Xml { line, message } => write!(f, "Failed parsing the XML on line {}! Reason: {}", line, message),
+            ConvertError::UnknownType { line, node } => write!(f, "Unknown param node <{}> on line {}!", node, line),
+            ConvertError::InvalidValue { field, value } => write!(f, "{} is not a valid value for {}!", value, field),
+            ConvertError::UnknownEntryKind(kind) => write!(f, "Could not figure out which entry type {} is!", kind),
+            ConvertError::NoRows => write!(f, "Did not find any rows to convert!"),
+            ConvertError::MissingPlaylist => write!(f, "Playlist rows need the id of the playlist they go in!"),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Hash40(Hash40),
+    String(String),
+    Byte(u8),
+    SByte(i8),
+    Short(i16),
+    UShort(u16),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+    Bool(bool),
+}
+
+impl ParamValue {
+    pub fn parse(node: &str, text: &str) -> Option<Self> {
+        let text = text.trim();
+        Some(match node {
+            "hash40" => ParamValue::Hash40(text.parse().ok()?),
+            "string" => ParamValue::String(text.to_string()),
+            "byte" => ParamValue::Byte(text.parse().ok()?),
+            "sbyte" => ParamValue::SByte(text.parse().ok()?),
+            "short" => ParamValue::Short(text.parse().ok()?),
+            "ushort" => ParamValue::UShort(text.parse().ok()?),
+            "int" => ParamValue::Int(text.parse().ok()?),
+            "uint" => ParamValue::UInt(text.parse().ok()?),
+            "float" => ParamValue::Float(text.parse().ok()?),
+            "bool" => ParamValue::Bool(text.to_lowercase().parse().ok()?),
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn as_i64(&self) -> Option<i64> {
+        Some(match *self {
+            ParamValue::Byte(v) => v as i64,
+            ParamValue::SByte(v) => v as i64,
+            ParamValue::Short(v) => v as i64,
+            ParamValue::UShort(v) => v as i64,
+            ParamValue::Int(v) => v as i64,
+            ParamValue::UInt(v) => v as i64,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamValue::Hash40(v) => write!(f, "{}", v),
+            ParamValue::String(v) => write!(f, "{:?}", v),
+            ParamValue::Byte(v) => write!(f, "{}", v),
+            ParamValue::SByte(v) => write!(f, "{}", v),
+            ParamValue::Short(v) => write!(f, "{}", v),
+            ParamValue::UShort(v) => write!(f, "{}", v),
+            ParamValue::Int(v) => write!(f, "{}", v),
+            ParamValue::UInt(v) => write!(f, "{}", v),
+            ParamValue::Float(v) => write!(f, "{:?}", v),
+            ParamValue::Bool(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// One `<struct>` of a paramxml list, in document order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamRow {
+    pub fields: Vec<(Hash40, ParamValue)>,
+    /// Keys and values written as plain labels. They aren't added to the label table, register them
+    /// with [`hash40::add_label`] to have the generated source name them.
+    pub labels: Vec<String>,
+}
+
+impl ParamRow {
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        let hash = hash40(name);
+        self.fields.iter().find(|(key, _)| *key == hash).map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+}
+
+pub trait FromParam: Sized {
+    fn from_param(value: &ParamValue, optional: bool) -> Option<Self>;
+}
+
+impl FromParam for u64 {
+    fn from_param(value: &ParamValue, _: bool) -> Option<Self> {
+        match value {
+            ParamValue::Hash40(hash) => Some(hash.0),
+            _ => None,
+        }
+    }
+}
+
+impl FromParam for Option<u64> {
+    fn from_param(value: &ParamValue, optional: bool) -> Option<Self> {
+        u64::from_param(value, optional).map(Some)
+    }
+}
+
+macro_rules! impl_from_param {
+    ($enum_type:ident, |$value:ident| $convert:expr) => {
+        impl FromParam for $enum_type {
+            fn from_param($value: &ParamValue, optional: bool) -> Option<Self> {
+                let converted = $convert?;
+                Some(if optional {
+                    $enum_type::Optional(Some(converted))
+                } else {
+                    $enum_type::Overwrite(converted)
+                })
+            }
+        }
+    };
+}
+
+impl_from_param!(StringType, |value| match value {
+    ParamValue::String(s) if !s.contains('\0') => Some(CStrCSK::new(s)),
+    _ => None,
+});
+impl_from_param!(Hash40Type, |value| u64::from_param(value, false));
+impl_from_param!(ShortType, |value| value.as_i64().and_then(|v| i16::try_from(v).ok()));
+impl_from_param!(UnsignedShortType, |value| value.as_i64().and_then(|v| u16::try_from(v).ok()));
+impl_from_param!(IntType, |value| value.as_i64().and_then(|v| i32::try_from(v).ok()));
+impl_from_param!(UnsignedIntType, |value| value.as_i64().and_then(|v| u32::try_from(v).ok()));
+impl_from_param!(SignedByteType, |value| value.as_i64().and_then(|v| i8::try_from(v).ok()));
+impl_from_param!(UnsignedByteType, |value| value.as_i64().and_then(|v| u8::try_from(v).ok()));
+impl_from_param!(FloatType, |value| match *value {
+    ParamValue::Float(v) => Some(v),
+    _ => value.as_i64().map(|v| v as f32),
+});
+impl_from_param!(BoolType, |value| match *value {
+    ParamValue::Bool(v) => Some(v),
+    _ => None,
+});
+
+impl FromParam for Hash40Map {
+    fn from_param(_: &ParamValue, _: bool) -> Option<Self> {
+        None
+    }
+}
+
+impl FromParam for UnsignedByteMap {
+    fn from_param(_: &ParamValue, _: bool) -> Option<Self> {
+        None
+    }
+}
+
+/// Renders a field as a Rust expression, or `None` when it is left at its default.
+pub trait RustSource {
+    fn to_rust(&self) -> Option<String>;
+}
+
+fn hash_source(hash: u64) -> String {
+    match Hash40(hash).label() {
+        Some(label) if !label.is_empty() => format!("{:#012x} /* Hash40 of {} */", hash, label),
+        _ => format!("{:#012x}", hash),
+    }
+}
+
+impl RustSource for u64 {
+    fn to_rust(&self) -> Option<String> {
+        Some(hash_source(*self))
+    }
+}
+
+impl RustSource for Option<u64> {
+    fn to_rust(&self) -> Option<String> {
+        self.map(|hash| format!("Some({})", hash_source(hash)))
+    }
+}
+
+macro_rules! impl_rust_source {
+    ($enum_type:ident, |$value:ident| $render:expr) => {
+        impl RustSource for $enum_type {
+            fn to_rust(&self) -> Option<String> {
+                let (method, value) = match self {
+                    $enum_type::Overwrite($value) => ("Overwrite", $render),
+                    $enum_type::Optional(Some($value)) => ("Optional", format!("Some({})", $render)),
+                    $enum_type::Optional(None) => return None,
+                };
+                Some(format!("{}::{}::{}({})", CRATE_PREFIX, stringify!($enum_type), method, value))
+            }
+        }
+    };
+}
+
+impl_rust_source!(StringType, |value| format!(
+    "{}::CStrCSK::new({:?})",
+    CRATE_PREFIX,
+    value.as_str().unwrap_or_default()
+));
+impl_rust_source!(Hash40Type, |value| hash_source(*value));
+impl_rust_source!(ShortType, |value| value.to_string());
+impl_rust_source!(UnsignedShortType, |value| value.to_string());
+impl_rust_source!(IntType, |value| value.to_string());
+impl_rust_source!(UnsignedIntType, |value| value.to_string());
+impl_rust_source!(SignedByteType, |value| value.to_string());
+impl_rust_source!(UnsignedByteType, |value| value.to_string());
+impl_rust_source!(FloatType, |value| format!("{:?}", value));
+impl_rust_source!(BoolType, |value| value.to_string());
+impl_rust_source!(Hash40Map, |value| map_source(value));
+impl_rust_source!(UnsignedByteMap, |value| map_source(value));
+
+fn map_source<T: RustSource>(map: &HashMap<u64, T>) -> String {
+    let mut keys: Vec<&u64> = map.keys().collect();
+    keys.sort();
+    let items: Vec<String> = keys
+        .into_iter()
+        .filter_map(|key| Some(format!("        ({}, {}),", hash_source(*key), map[key].to_rust()?)))
+        .collect();
+    format!("HashMap::from([\n{}\n    ])", items.join("\n"))
+}
+
+/// Implemented for every entry struct by `create_entry!`.
+pub trait ParamFields {
+    const NAME: &'static str;
+    const FIELDS: &'static [&'static str];
+
+    // Returns false if the entry has no field with that name
+    fn set_field(&mut self, name: &str, value: &ParamValue, optional: bool) -> Result<bool, ConvertError>;
+
+    fn rust_fields(&self) -> Vec<(&'static str, String)>;
+}
+
+pub trait ParamEntry: ParamFields + Default {
+    // Called for row fields that don't match a struct field, returns false if the value was dropped
+    fn set_extra(&mut self, _hash: u64, _value: &ParamValue) -> bool {
+        false
+    }
+
+    fn from_row(row: &ParamRow) -> Result<Self, ConvertError> {
+        entry_from_row(row, OPTIONAL_KEYS)
+    }
+
+    /// Like [`ParamEntry::from_row`], but every value is `Overwrite`, the way the game's own entries are read back.
+    fn from_row_overwriting(row: &ParamRow) -> Result<Self, ConvertError> {
+        entry_from_row(row, &[])
+    }
+
+    fn to_rust_source(&self) -> String {
+        let mut fields: Vec<String> = self
+            .rust_fields()
+            .into_iter()
+            .map(|(name, source)| format!("    {}: {},", name, source))
+            .collect();
+        fields.push("    ..Default::default()".to_string());
+        format!("{}::{} {{\n{}\n}}", CRATE_PREFIX, Self::NAME, fields.join("\n"))
+    }
+}
+
+fn entry_from_row<T: ParamEntry>(row: &ParamRow, optional_keys: &[&str]) -> Result<T, ConvertError> {
+    let mut entry = T::default();
+    for (hash, value) in row.fields.iter() {
+        let name = field_name::<T>(*hash);
+        let optional = optional_keys.contains(&name.as_str());
+        if !entry.set_field(&name, value, optional)? {
+            entry.set_extra(hash.0, value);
+        }
+    }
+    Ok(entry)
+}
+
+// Resolves a row key to the struct field name it belongs to, falling back to the unk_0x.. naming
+fn field_name<T: ParamFields>(hash: Hash40) -> String {
+    if let Some(field) = T::FIELDS.iter().find(|field| hash40(field) == hash) {
+        return field.to_string();
+    }
+    match hash.label() {
+        Some(label) => label,
+        None => format!("unk_{:#012x}", hash.0),
+    }
+}
+
+impl ParamEntry for CharacterDatabaseEntry {
+    fn set_extra(&mut self, hash: u64, value: &ParamValue) -> bool {
+        match value {
+            ParamValue::Hash40(_) => {
+                if let Hash40Map::Optional(None) = self.extra_hash_maps {
+                    self.extra_hash_maps = Hash40Map::Overwrite(HashMap::new());
+                }
+                match (&mut self.extra_hash_maps, Hash40Type::from_param(value, false)) {
+                    (Hash40Map::Overwrite(map), Some(value)) => map.insert(hash, value).is_none(),
+                    _ => false,
+                }
+            }
+            ParamValue::Byte(_) => {
+                if let UnsignedByteMap::Optional(None) = self.extra_index_maps {
+                    self.extra_index_maps = UnsignedByteMap::Overwrite(HashMap::new());
+                }
+                match (&mut self.extra_index_maps, UnsignedByteType::from_param(value, false)) {
+                    (UnsignedByteMap::Overwrite(map), Some(value)) => map.insert(hash, value).is_none(),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl ParamEntry for StageDatabaseEntry {
+    fn set_extra(&mut self, hash: u64, value: &ParamValue) -> bool {
+        if let Hash40Map::Optional(None) = self.extra_hash_maps {
+            self.extra_hash_maps = Hash40Map::Overwrite(HashMap::new());
+        }
+        match (&mut self.extra_hash_maps, Hash40Type::from_param(value, false)) {
+            (Hash40Map::Overwrite(map), Some(value)) => map.insert(hash, value).is_none(),
+            _ => false,
+        }
+    }
+}
+
+impl ParamEntry for CharacterLayoutDatabaseEntry {}
+impl ParamEntry for SeriesDatabaseEntry {}
+impl ParamEntry for BgmDatabaseRootEntry {}
+impl ParamEntry for StreamSetEntry {}
+impl ParamEntry for AssignedInfoEntry {}
+impl ParamEntry for StreamPropertyEntry {}
+impl ParamEntry for BgmPlaylistEntry {}
+impl ParamEntry for GametitleDatabaseEntry {}
+impl ParamEntry for TipsDatabaseEntry {}
+impl ParamEntry for AmiiboDatabaseEntry {}
+impl ParamEntry for MiiBodyDatabaseEntry {}
+impl ParamEntry for MiiHatDatabaseEntry {}
+
+pub fn convert_rows<T: ParamEntry>(rows: &[ParamRow]) -> Result<Vec<T>, ConvertError> {
+    rows.iter().map(T::from_row).collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    Chara,
+    CharaLayout,
+    Series,
+    Bgm,
+    StreamSet,
+    AssignedInfo,
+    StreamProperty,
+    BgmPlaylist,
+    Stage,
+    Gametitle,
+    Tips,
+    Amiibo,
+    MiiBody,
+    MiiHat,
+}
+
+impl EntryKind {
+    pub const ALL: [EntryKind; 14] = [
+        EntryKind::Chara,
+        EntryKind::CharaLayout,
+        EntryKind::Series,
+        EntryKind::Bgm,
+        EntryKind::StreamSet,
+        EntryKind::AssignedInfo,
+        EntryKind::StreamProperty,
+        EntryKind::BgmPlaylist,
+        EntryKind::Stage,
+        EntryKind::Gametitle,
+        EntryKind::Tips,
+        EntryKind::Amiibo,
+        EntryKind::MiiBody,
+        EntryKind::MiiHat,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EntryKind::Chara => "chara",
+            EntryKind::CharaLayout => "chara_layout",
+            EntryKind::Series => "series",
+            EntryKind::Bgm => "bgm",
+            EntryKind::StreamSet => "stream_set",
+            EntryKind::AssignedInfo => "assigned_info",
+            EntryKind::StreamProperty => "stream_property",
+            EntryKind::BgmPlaylist => "bgm_playlist",
+            EntryKind::Stage => "stage",
+            EntryKind::Gametitle => "gametitle",
+            EntryKind::Tips => "tips",
+            EntryKind::Amiibo => "amiibo",
+            EntryKind::MiiBody => "mii_body",
+            EntryKind::MiiHat => "mii_hat",
+        }
+    }
+
+    // Guesses the entry type from the id field of the row
+    pub fn detect(row: &ParamRow) -> Option<Self> {
+        Some(if row.contains("ui_layout_id") {
+            EntryKind::CharaLayout
+        } else if row.contains("ui_chara_id") && row.contains("fighter_kind") {
+            EntryKind::Chara
+        } else if row.contains("ui_amiibo_id") {
+            EntryKind::Amiibo
+        } else if row.contains("ui_stage_id") {
+            EntryKind::Stage
+        } else if row.contains("ui_gametitle_id") && !row.contains("ui_bgm_id") {
+            EntryKind::Gametitle
+        } else if row.contains("ui_series_id") {
+            EntryKind::Series
+        } else if row.contains("ui_bgm_id") && row.contains("stream_set_id") {
+            EntryKind::Bgm
+        } else if row.contains("ui_bgm_id") {
+            EntryKind::BgmPlaylist
+        } else if row.contains("stream_set_id") {
+            EntryKind::StreamSet
+        } else if row.contains("info_id") {
+            EntryKind::AssignedInfo
+        } else if row.contains("stream_id") {
+            EntryKind::StreamProperty
+        } else if row.contains("ui_tips_id") {
+            EntryKind::Tips
+        } else if row.contains("ui_mii_body_id") {
+            EntryKind::MiiBody
+        } else if row.contains("ui_mii_hat_id") {
+            EntryKind::MiiHat
+        } else {
+            return None;
+        })
+    }
+}
+
+impl std::str::FromStr for EntryKind {
+    type Err = ConvertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EntryKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| ConvertError::UnknownEntryKind(s.to_string()))
+    }
+}
+
+fn rows_to_rust_typed<T: ParamEntry>(rows: &[ParamRow]) -> Result<Vec<String>, ConvertError> {
+    Ok(convert_rows::<T>(rows)?.iter().map(T::to_rust_source).collect())
+}
+
+/// Converts every row into Rust source for the given entry type, one struct expression per row.
+pub fn rows_to_rust(kind: EntryKind, rows: &[ParamRow]) -> Result<Vec<String>, ConvertError> {
+    match kind {
+        EntryKind::Chara => rows_to_rust_typed::<CharacterDatabaseEntry>(rows),
+        EntryKind::CharaLayout => rows_to_rust_typed::<CharacterLayoutDatabaseEntry>(rows),
+        EntryKind::Series => rows_to_rust_typed::<SeriesDatabaseEntry>(rows),
+        EntryKind::Bgm => rows_to_rust_typed::<BgmDatabaseRootEntry>(rows),
+        EntryKind::StreamSet => rows_to_rust_typed::<StreamSetEntry>(rows),
+        EntryKind::AssignedInfo => rows_to_rust_typed::<AssignedInfoEntry>(rows),
+        EntryKind::StreamProperty => rows_to_rust_typed::<StreamPropertyEntry>(rows),
+        EntryKind::BgmPlaylist => rows_to_rust_typed::<BgmPlaylistEntry>(rows),
+        EntryKind::Stage => rows_to_rust_typed::<StageDatabaseEntry>(rows),
+        EntryKind::Gametitle => rows_to_rust_typed::<GametitleDatabaseEntry>(rows),
+        EntryKind::Tips => rows_to_rust_typed::<TipsDatabaseEntry>(rows),
+        EntryKind::Amiibo => rows_to_rust_typed::<AmiiboDatabaseEntry>(rows),
+        EntryKind::MiiBody => rows_to_rust_typed::<MiiBodyDatabaseEntry>(rows),
+        EntryKind::MiiHat => rows_to_rust_typed::<MiiHatDatabaseEntry>(rows),
+    }
+}
+
+fn rows_to_debug_typed<T: ParamEntry + fmt::Debug>(rows: &[ParamRow]) -> Result<Vec<String>, ConvertError> {
+    Ok(convert_rows::<T>(rows)?.iter().map(|entry| format!("{:#?}", entry)).collect())
+}
+
+/// Converts every row into the `Debug` representation of the entry struct.
+pub fn rows_to_debug(kind: EntryKind, rows: &[ParamRow]) -> Result<Vec<String>, ConvertError> {
+    match kind {
+        EntryKind::Chara => rows_to_debug_typed::<CharacterDatabaseEntry>(rows),
+        EntryKind::CharaLayout => rows_to_debug_typed::<CharacterLayoutDatabaseEntry>(rows),
+        EntryKind::Series => rows_to_debug_typed::<SeriesDatabaseEntry>(rows),
+        EntryKind::Bgm => rows_to_debug_typed::<BgmDatabaseRootEntry>(rows),
+        EntryKind::StreamSet => rows_to_debug_typed::<StreamSetEntry>(rows),
+        EntryKind::AssignedInfo => rows_to_debug_typed::<AssignedInfoEntry>(rows),
+        EntryKind::StreamProperty => rows_to_debug_typed::<StreamPropertyEntry>(rows),
+        EntryKind::BgmPlaylist => rows_to_debug_typed::<BgmPlaylistEntry>(rows),
+        EntryKind::Stage => rows_to_debug_typed::<StageDatabaseEntry>(rows),
+        EntryKind::Gametitle => rows_to_debug_typed::<GametitleDatabaseEntry>(rows),
+        EntryKind::Tips => rows_to_debug_typed::<TipsDatabaseEntry>(rows),
+        EntryKind::Amiibo => rows_to_debug_typed::<AmiiboDatabaseEntry>(rows),
+        EntryKind::MiiBody => rows_to_debug_typed::<MiiBodyDatabaseEntry>(rows),
+        EntryKind::MiiHat => rows_to_debug_typed::<MiiHatDatabaseEntry>(rows),
+    }
+}
+
+// Plain labels are kept so the caller can name them in the generated source
+fn push_label(labels: &mut Vec<String>, text: &str) {
+    let text = text.trim();
+    if !text.is_empty() && !text.starts_with("0x") && !text.starts_with("0X") {
+        labels.push(text.to_string());
+    }
+}
+
+/// Converts every row into a manifest holding only the given entry type. Playlist rows are put in `playlist`,
+/// which they can't go without.
+#[cfg(feature = "serde")]
+pub fn rows_to_manifest(
+    kind: EntryKind,
+    rows: &[ParamRow],
+    playlist: Option<Hash40>,
+) -> Result<crate::manifest::Manifest, ConvertError> {
+    let mut manifest = crate::manifest::Manifest::default();
+    match kind {
+        EntryKind::Chara => manifest.chara_db = convert_rows(rows)?,
+        EntryKind::CharaLayout => manifest.chara_layout_db = convert_rows(rows)?,
+        EntryKind::Series => manifest.series_db = convert_rows(rows)?,
+        EntryKind::Bgm => manifest.bgm_db = convert_rows(rows)?,
+        EntryKind::StreamSet => manifest.stream_set = convert_rows(rows)?,
+        EntryKind::AssignedInfo => manifest.assigned_info = convert_rows(rows)?,
+        EntryKind::StreamProperty => manifest.stream_property = convert_rows(rows)?,
+        EntryKind::BgmPlaylist => manifest.playlists.push(crate::manifest::PlaylistManifest {
+            playlist: playlist.ok_or(ConvertError::MissingPlaylist)?,
+            tracks: convert_rows(rows)?,
+        }),
+        EntryKind::Stage => manifest.stage_db = convert_rows(rows)?,
+        EntryKind::Gametitle => manifest.gametitle_db = convert_rows(rows)?,
+        EntryKind::Tips => manifest.tips_db = convert_rows(rows)?,
+        EntryKind::Amiibo => manifest.amiibo_db = convert_rows(rows)?,
+        EntryKind::MiiBody => manifest.mii_body_db = convert_rows(rows)?,
+        EntryKind::MiiHat => manifest.mii_hat_db = convert_rows(rows)?,
+    }
+    Ok(manifest)
+}
+
+enum XmlEvent<'a> {
+    Start { name: &'a str, attrs: Vec<(&'a str, String)> },
+    End { name: &'a str },
+    Text(String),
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn parse_attrs(mut text: &str, line: usize) -> Result<Vec<(&str, String)>, ConvertError> {
+    let mut attrs = Vec::new();
+    loop {
+        text = text.trim_start();
+        if text.is_empty() {
+            return Ok(attrs);
+        }
+        let (name, rest) = text.split_once('=').ok_or_else(|| ConvertError::Xml {
+            line,
+            message: format!("Malformed attribute {}", text),
+        })?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'').ok_or_else(|| ConvertError::Xml {
+            line,
+            message: format!("Unquoted attribute {}", name.trim()),
+        })?;
+        let end = rest[1..].find(quote).ok_or_else(|| ConvertError::Xml {
+            line,
+            message: format!("Unterminated attribute {}", name.trim()),
+        })?;
+        attrs.push((name.trim(), unescape(&rest[1..end + 1])));
+        text = &rest[end + 2..];
+    }
+}
+
+// Splits the document into tags and text, skipping declarations and comments
+fn tokenize(xml: &str) -> Result<Vec<(usize, XmlEvent<'_>)>, ConvertError> {
+    let mut events = Vec::new();
+    let mut rest = xml;
+    let mut line = 1;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            if !rest.trim().is_empty() {
+                events.push((line, XmlEvent::Text(unescape(rest))));
+            }
+            break;
+        };
+        if !rest[..start].trim().is_empty() {
+            events.push((line, XmlEvent::Text(unescape(&rest[..start]))));
+        }
+        line += rest[..start].matches('\n').count();
+        rest = &rest[start..];
+
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<?") {
+            "?>"
+        } else {
+            ">"
+        };
+        let end = rest.find(terminator).ok_or_else(|| ConvertError::Xml {
+            line,
+            message: "Unterminated tag".to_string(),
+        })?;
+        let tag = &rest[..end + terminator.len()];
+        let tag_line = line;
+        line += tag.matches('\n').count();
+        rest = &rest[end + terminator.len()..];
+
+        if tag.starts_with("<!") || tag.starts_with("<?") {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix("</") {
+            events.push((tag_line, XmlEvent::End { name: name.trim_end_matches('>').trim() }));
+            continue;
+        }
+        let (inner, empty) = match tag[1..tag.len() - 1].strip_suffix('/') {
+            Some(inner) => (inner, true),
+            None => (&tag[1..tag.len() - 1], false),
+        };
+        let (name, attrs) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+        events.push((tag_line, XmlEvent::Start { name, attrs: parse_attrs(attrs, tag_line)? }));
+        // `<string hash=".."/>` is an empty value, so it's read like `<string hash=".."></string>`
+        if empty {
+            events.push((tag_line, XmlEvent::End { name }));
+        }
+    }
+    Ok(events)
+}
+
+/// Parses every `<struct>` holding value nodes into a row. Value nodes outside of any struct
+/// (e.g. a few lines pasted out of a row) are collected into a single row.
+pub fn parse_rows(xml: &str) -> Result<Vec<ParamRow>, ConvertError> {
+    let mut rows = Vec::new();
+    let mut loose = ParamRow::default();
+    // Open structs, with None for lists and other containers
+    let mut stack: Vec<Option<ParamRow>> = Vec::new();
+    // (line, node, hash, hash as written, text)
+    let mut value: Option<(usize, &str, Hash40, String, String)> = None;
+    let mut labels = Vec::new();
+
+    for (line, event) in tokenize(xml)? {
+        match event {
+            XmlEvent::Start { name, attrs } => {
+                if value.is_some() {
+                    return Err(ConvertError::Xml { line, message: format!("Unexpected <{}> inside a value node", name) });
+                }
+                match name {
+                    "struct" => stack.push(Some(ParamRow::default())),
+                    "list" => stack.push(None),
+                    _ => {
+                        let key = attrs.into_iter().find(|(key, _)| *key == "hash").map(|(_, key)| key).unwrap_or_default();
+                        let hash = match key.as_str() {
+                            "" => Hash40(0),
+                            key => key.parse().map_err(|err: hash40::ParseHash40Error| ConvertError::Xml {
+                                line,
+                                message: err.to_string(),
+                            })?,
+                        };
+                        push_label(&mut labels, &key);
+                        value = Some((line, name, hash, key, String::new()));
+                    }
+                }
+            }
+            XmlEvent::Text(text) => {
+                if let Some((_, _, _, _, buffer)) = value.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            XmlEvent::End { name } => match value.take() {
+                Some((value_line, node, hash, key, text)) => {
+                    if node != name {
+                        return Err(ConvertError::Xml { line, message: format!("Expected </{}>, found </{}>", node, name) });
+                    }
+                    let parsed = ParamValue::parse(node, &text).ok_or(if matches!(
+                        node,
+                        "hash40" | "string" | "byte" | "sbyte" | "short" | "ushort" | "int" | "uint" | "float" | "bool"
+                    ) {
+                        ConvertError::InvalidValue { field: key, value: text.trim().to_string() }
+                    } else {
+                        ConvertError::UnknownType { line: value_line, node: node.to_string() }
+                    })?;
+                    if node == "hash40" {
+                        push_label(&mut labels, &text);
+                    }
+                    let row = match stack.last_mut() {
+                        Some(Some(row)) => row,
+                        Some(None) => {
+                            labels.clear();
+                            continue;
+                        }
+                        None => &mut loose,
+                    };
+                    row.fields.push((hash, parsed));
+                    for label in labels.drain(..) {
+                        if !row.labels.contains(&label) {
+                            row.labels.push(label);
+                        }
+                    }
+                }
+                None => match stack.pop() {
+                    Some(Some(row)) if !row.fields.is_empty() => rows.push(row),
+                    Some(_) => {}
+                    None => return Err(ConvertError::Xml { line, message: format!("Unexpected </{}>", name) }),
+                },
+            },
+        }
+    }
+
+    if !loose.fields.is_empty() {
+        rows.insert(0, loose);
+    }
+    Ok(rows)
+}
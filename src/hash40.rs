@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+const CRC_TABLE: [u32; 256] = make_crc_table();
+
+const fn make_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    let mut i = 0;
+    while i < bytes.len() {
+        crc = (crc >> 8) ^ CRC_TABLE[((crc ^ bytes[i] as u32) & 0xFF) as usize];
+        i += 1;
+    }
+    !crc
+}
+
+/// Hashes `label` the same way the game does: the length in the upper byte and the CRC32 in the lower four.
+pub const fn hash40(label: &str) -> Hash40 {
+    let bytes = label.as_bytes();
+    Hash40(((bytes.len() as u64 & 0xFF) << 32) | crc32(bytes) as u64)
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hash40(pub u64);
+
+impl Hash40 {
+    pub const fn new(label: &str) -> Self {
+        hash40(label)
+    }
+
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    // Looks the hash up in the loaded label table
+    pub fn label(self) -> Option<String> {
+        labels().read().unwrap().get(&self.0).cloned()
+    }
+}
+
+impl fmt::Display for Hash40 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label() {
+            Some(label) => f.write_str(&label),
+            None => write!(f, "{:#012x}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for Hash40 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label() {
+            Some(label) => write!(f, "Hash40({:?})", label),
+            None => write!(f, "Hash40({:#012x})", self.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHash40Error(pub String);
+
+impl fmt::Display for ParseHash40Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not a valid hex Hash40!", self.0)
+    }
+}
+
+impl std::error::Error for ParseHash40Error {}
+
+impl FromStr for Hash40 {
+    type Err = ParseHash40Error;
+
+    /// Accepts either a hex value (`0x0a1b2c3d4e`) or a plain label (`ui_chara_mario`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match strip_hex_prefix(s) {
+            Some(hex) => u64::from_str_radix(hex, 16)
+                .map(Hash40)
+                .map_err(|_| ParseHash40Error(s.to_string())),
+            None => Ok(hash40(s)),
+        }
+    }
+}
+
+fn strip_hex_prefix(s: &str) -> Option<&str> {
+    s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))
+}
+
+impl From<u64> for Hash40 {
+    fn from(hash: u64) -> Self {
+        Hash40(hash)
+    }
+}
+
+impl From<Hash40> for u64 {
+    fn from(hash: Hash40) -> Self {
+        hash.0
+    }
+}
+
+impl From<&str> for Hash40 {
+    fn from(label: &str) -> Self {
+        hash40(label)
+    }
+}
+
+impl From<Hash40> for crate::Hash40Type {
+    fn from(hash: Hash40) -> Self {
+        crate::Hash40Type::Overwrite(hash.0)
+    }
+}
+
+fn labels() -> &'static RwLock<HashMap<u64, String>> {
+    static LABELS: OnceLock<RwLock<HashMap<u64, String>>> = OnceLock::new();
+    LABELS.get_or_init(Default::default)
+}
+
+/// Registers a single label for reverse lookup and returns its hash.
+pub fn add_label(label: &str) -> Hash40 {
+    let hash = hash40(label);
+    labels().write().unwrap().insert(hash.0, label.to_string());
+    hash
+}
+
+/// Loads labels in the ParamLabels.csv format (`0x0a1b2c3d4e,label`, one per line).
+/// Lines without a hash column are hashed directly. Returns the amount of labels added.
+pub fn load_labels_from_str(text: &str) -> usize {
+    let mut table = labels().write().unwrap();
+    let mut count = 0;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (hash, label) = match line.split_once(',') {
+            Some((hash, label)) => match strip_hex_prefix(hash.trim()) {
+                Some(hex) => match u64::from_str_radix(hex, 16) {
+                    Ok(hash) => (Hash40(hash), label.trim()),
+                    Err(_) => continue,
+                },
+                None => continue,
+            },
+            None => (hash40(line), line),
+        };
+        table.insert(hash.0, label.to_string());
+        count += 1;
+    }
+    count
+}
+
+pub fn load_labels<P: AsRef<Path>>(path: P) -> std::io::Result<usize> {
+    Ok(load_labels_from_str(&std::fs::read_to_string(path)?))
+}
+
+pub fn clear_labels() {
+    labels().write().unwrap().clear();
+}
@@ -0,0 +1,249 @@
+//! A structured view of [`CharacterLayoutDatabaseEntry`].
+//!
+//! The flat entry has a field per eye flash coordinate and portrait offset, in the order the plugin
+//! reads them. [`CharacterLayout`] groups them into the three eye flash sets and a map of portraits,
+//! and converts back without losing anything, `Optional` values included:
+//!
+//! ```ignore
+//! let mut layout = CharacterLayout::from(entry);
+//! if let Some(portrait) = layout.portrait_mut(PortraitKind::Chara3(0)) {
+//!     portrait.scale = FloatType::Overwrite(1.1);
+//! }
+//! let entry = CharacterLayoutDatabaseEntry::from(layout);
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{BoolType, CharacterLayoutDatabaseEntry, FloatType, Hash40Type, UnsignedByteType};
+
+#[derive(Debug, Clone, Default)]
+pub struct Vec2 {
+    pub x: FloatType,
+    pub y: FloatType,
+}
+
+impl Vec2 {
+    pub fn new(x: f32, y: f32) -> Self {
+        Vec2 { x: FloatType::Overwrite(x), y: FloatType::Overwrite(y) }
+    }
+
+    /// Both coordinates, if both are set.
+    pub fn get(&self) -> Option<(f32, f32)> {
+        Some((*self.x.value()?, *self.y.value()?))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EyeFlash {
+    pub count: UnsignedByteType,
+    pub positions: [Vec2; 5],
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Portrait {
+    pub offset: Vec2,
+    pub scale: FloatType,
+}
+
+/// The portraits a layout places, by the `chara_N` name of their fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PortraitKind {
+    Chara0,
+    /// `chara_1` for 0, `chara_1_1` to `chara_1_5` after.
+    Chara1(u8),
+    /// `chara_3_0` to `chara_3_7`
+    Chara3(u8),
+    Chara5,
+    /// `chara_7_0` and `chara_7_1`
+    Chara7(u8),
+    SelectIconList,
+}
+
+impl PortraitKind {
+    pub const ALL: [PortraitKind; 19] = [
+        PortraitKind::Chara0,
+        PortraitKind::Chara1(0),
+        PortraitKind::Chara1(1),
+        PortraitKind::Chara1(2),
+        PortraitKind::Chara1(3),
+        PortraitKind::Chara1(4),
+        PortraitKind::Chara1(5),
+        PortraitKind::Chara3(0),
+        PortraitKind::Chara3(1),
+        PortraitKind::Chara3(2),
+        PortraitKind::Chara3(3),
+        PortraitKind::Chara3(4),
+        PortraitKind::Chara3(5),
+        PortraitKind::Chara3(6),
+        PortraitKind::Chara3(7),
+        PortraitKind::Chara5,
+        PortraitKind::Chara7(0),
+        PortraitKind::Chara7(1),
+        PortraitKind::SelectIconList,
+    ];
+
+    /// Whether the layout has fields for this portrait, e.g. there's no `chara_3_8`.
+    pub fn is_valid(&self) -> bool {
+        PortraitKind::ALL.contains(self)
+    }
+}
+
+impl fmt::Display for PortraitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortraitKind::Chara0 => write!(f, "chara_0"),
+            PortraitKind::Chara1(0) => write!(f, "chara_1"),
+            PortraitKind::Chara1(index) => write!(f, "chara_1_{}", index),
+            PortraitKind::Chara3(index) => write!(f, "chara_3_{}", index),
+            PortraitKind::Chara5 => write!(f, "chara_5"),
+            PortraitKind::Chara7(index) => write!(f, "chara_7_{}", index),
+            PortraitKind::SelectIconList => write!(f, "chara_select_icon_list"),
+        }
+    }
+}
+
+/// [`CharacterLayoutDatabaseEntry`] with its eye flashes and portraits grouped.
+#[derive(Debug, Clone, Default)]
+pub struct CharacterLayout {
+    pub ui_layout_id: u64,
+    pub clone_from_ui_layout_id: Option<u64>,
+    pub ui_chara_id: Hash40Type,
+    pub chara_color: UnsignedByteType,
+    pub eyes: [EyeFlash; 3],
+    pub eye_flash_info_pos: Vec2,
+    pub spirits_eye_visible: BoolType,
+    // Kinds that aren't valid are dropped when converting back
+    pub portraits: BTreeMap<PortraitKind, Portrait>,
+}
+
+impl CharacterLayout {
+    /// The portrait of `kind`, unset if the layout doesn't have it.
+    pub fn portrait(&self, kind: PortraitKind) -> Portrait {
+        self.portraits.get(&kind).cloned().unwrap_or_default()
+    }
+
+    /// The portrait of `kind` to edit, or `None` if the layout has no fields for it, e.g. `Chara3(8)`.
+    pub fn portrait_mut(&mut self, kind: PortraitKind) -> Option<&mut Portrait> {
+        if !kind.is_valid() {
+            return None;
+        }
+        Some(self.portraits.entry(kind).or_default())
+    }
+}
+
+// Maps the grouped layout onto the flat fields:
+// `eye index: count field, [x field, y field; ...];` then `portrait kind => x field, y field, scale field;`
+macro_rules! layout_fields {
+    (
+        eyes { $($eye:literal: $count:ident, [$($x:ident, $y:ident);*];)* }
+        portraits { $($kind:expr => $offset_x:ident, $offset_y:ident, $scale:ident;)* }
+    ) => {
+        impl From<CharacterLayoutDatabaseEntry> for CharacterLayout {
+            fn from(entry: CharacterLayoutDatabaseEntry) -> Self {
+                let mut portraits = BTreeMap::new();
+                $(
+                    portraits.insert($kind, Portrait {
+                        offset: Vec2 { x: entry.$offset_x, y: entry.$offset_y },
+                        scale: entry.$scale,
+                    });
+                )*
+                CharacterLayout {
+                    ui_layout_id: entry.ui_layout_id,
+                    clone_from_ui_layout_id: entry.clone_from_ui_layout_id,
+                    ui_chara_id: entry.ui_chara_id,
+                    chara_color: entry.chara_color,
+                    eyes: [$(
+                        EyeFlash {
+                            count: entry.$count,
+                            positions: [$(Vec2 { x: entry.$x, y: entry.$y }),*],
+                        }
+                    ),*],
+                    eye_flash_info_pos: Vec2 { x: entry.eye_flash_info_pos_x, y: entry.eye_flash_info_pos_y },
+                    spirits_eye_visible: entry.spirits_eye_visible,
+                    portraits,
+                }
+            }
+        }
+
+        impl From<CharacterLayout> for CharacterLayoutDatabaseEntry {
+            fn from(mut layout: CharacterLayout) -> Self {
+                let mut entry = CharacterLayoutDatabaseEntry {
+                    ui_layout_id: layout.ui_layout_id,
+                    clone_from_ui_layout_id: layout.clone_from_ui_layout_id,
+                    ui_chara_id: layout.ui_chara_id,
+                    chara_color: layout.chara_color,
+                    eye_flash_info_pos_x: layout.eye_flash_info_pos.x,
+                    eye_flash_info_pos_y: layout.eye_flash_info_pos.y,
+                    spirits_eye_visible: layout.spirits_eye_visible,
+                    ..Default::default()
+                };
+                $(
+                    let EyeFlash { count, positions } = std::mem::take(&mut layout.eyes[$eye]);
+                    entry.$count = count;
+                    let mut positions = positions.into_iter();
+                    $(
+                        let Vec2 { x, y } = positions.next().unwrap_or_default();
+                        entry.$x = x;
+                        entry.$y = y;
+                    )*
+                )*
+                $(
+                    if let Some(Portrait { offset, scale }) = layout.portraits.remove(&$kind) {
+                        entry.$offset_x = offset.x;
+                        entry.$offset_y = offset.y;
+                        entry.$scale = scale;
+                    }
+                )*
+                entry
+            }
+        }
+    };
+}
+
+layout_fields! {
+    eyes {
+        0: eye_0_flash_count, [
+            eye_0_flash0_pos_x, eye_0_flash0_pos_y;
+            eye_0_flash1_pos_x, eye_0_flash1_pos_y;
+            eye_0_flash2_pos_x, eye_0_flash2_pos_y;
+            eye_0_flash3_pos_x, eye_0_flash3_pos_y;
+            eye_0_flash4_pos_x, eye_0_flash4_pos_y
+        ];
+        1: eye_1_flash_count, [
+            eye_1_flash0_pos_x, eye_1_flash0_pos_y;
+            eye_1_flash1_pos_x, eye_1_flash1_pos_y;
+            eye_1_flash2_pos_x, eye_1_flash2_pos_y;
+            eye_1_flash3_pos_x, eye_1_flash3_pos_y;
+            eye_1_flash4_pos_x, eye_1_flash4_pos_y
+        ];
+        2: eye_2_flash_count, [
+            eye_2_flash0_pos_x, eye_2_flash0_pos_y;
+            eye_2_flash1_pos_x, eye_2_flash1_pos_y;
+            eye_2_flash2_pos_x, eye_2_flash2_pos_y;
+            eye_2_flash3_pos_x, eye_2_flash3_pos_y;
+            eye_2_flash4_pos_x, eye_2_flash4_pos_y
+        ];
+    }
+    portraits {
+        PortraitKind::Chara0 => chara_0_offset_x, chara_0_offset_y, chara_0_scale;
+        PortraitKind::Chara1(0) => chara_1_offset_x, chara_1_offset_y, chara_1_scale;
+        PortraitKind::Chara1(1) => chara_1_1_offset_x, chara_1_1_offset_y, chara_1_1_scale;
+        PortraitKind::Chara1(2) => chara_1_2_offset_x, chara_1_2_offset_y, chara_1_2_scale;
+        PortraitKind::Chara1(3) => chara_1_3_offset_x, chara_1_3_offset_y, chara_1_3_scale;
+        PortraitKind::Chara1(4) => chara_1_4_offset_x, chara_1_4_offset_y, chara_1_4_scale;
+        PortraitKind::Chara1(5) => chara_1_5_offset_x, chara_1_5_offset_y, chara_1_5_scale;
+        PortraitKind::Chara3(0) => chara_3_0_offset_x, chara_3_0_offset_y, chara_3_0_scale;
+        PortraitKind::Chara3(1) => chara_3_1_offset_x, chara_3_1_offset_y, chara_3_1_scale;
+        PortraitKind::Chara3(2) => chara_3_2_offset_x, chara_3_2_offset_y, chara_3_2_scale;
+        PortraitKind::Chara3(3) => chara_3_3_offset_x, chara_3_3_offset_y, chara_3_3_scale;
+        PortraitKind::Chara3(4) => chara_3_4_offset_x, chara_3_4_offset_y, chara_3_4_scale;
+        PortraitKind::Chara3(5) => chara_3_5_offset_x, chara_3_5_offset_y, chara_3_5_scale;
+        PortraitKind::Chara3(6) => chara_3_6_offset_x, chara_3_6_offset_y, chara_3_6_scale;
+        PortraitKind::Chara3(7) => chara_3_7_offset_x, chara_3_7_offset_y, chara_3_7_scale;
+        PortraitKind::Chara5 => chara_5_offset_x, chara_5_offset_y, chara_5_scale;
+        PortraitKind::Chara7(0) => chara_7_0_offset_x, chara_7_0_offset_y, chara_7_0_scale;
+        PortraitKind::Chara7(1) => chara_7_1_offset_x, chara_7_1_offset_y, chara_7_1_scale;
+        PortraitKind::SelectIconList =>
+            chara_select_icon_list_offset_x, chara_select_icon_list_offset_y, chara_select_icon_list_scale;
+    }
+}
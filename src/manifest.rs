@@ -0,0 +1,170 @@
+//! Mod manifests: every entry a mod adds, described in a TOML or JSON file instead of Rust code.
+//!
+//! ```toml
+//! narration_characall = ["vc_narration_characall_custom"]
+//!
+//! [[chara_db]]
+//! ui_chara_id = "ui_chara_custom"
+//! clone_from_ui_chara_id = "ui_chara_mario"
+//! name_id = "custom"
+//! disp_order = { optional = 90 }
+//! ```
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{Batch, BatchError};
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, Hash40, MiiBodyDatabaseEntry, MiiHatDatabaseEntry,
+    SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, TipsDatabaseEntry, UiStageData,
+};
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(PathBuf, std::io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    UnknownFormat(PathBuf),
+    Batch(BatchError),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io(path, err) => write!(f, "Failed reading {}! Reason: {}", path.display(), err),
+            ManifestError::Toml(err) => write!(f, "Failed parsing the TOML manifest! Reason: {}", err),
+            ManifestError::Json(err) => write!(f, "Failed parsing the JSON manifest! Reason: {}", err),
+            ManifestError::UnknownFormat(path) => {
+                write!(f, "{} is not a .toml or .json manifest!", path.display())
+            }
+            ManifestError::Batch(err) => write!(f, "Failed registering the manifest! Reason: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaylistManifest {
+    pub playlist: Hash40,
+    pub tracks: Vec<BgmPlaylistEntry>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct UiStageResourcesManifest {
+    pub stage_place_id: Hash40,
+    pub ui_stage_id: Hash40,
+    #[serde(flatten)]
+    pub data: UiStageData,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FighterJingleManifest {
+    pub ui_chara_id: Hash40,
+    #[serde(deserialize_with = "crate::serialization::c_string")]
+    pub jingle: String,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub series_db: Vec<SeriesDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gametitle_db: Vec<GametitleDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chara_db: Vec<CharacterDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chara_layout_db: Vec<CharacterLayoutDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub online_charas: Vec<Hash40>,
+    #[serde(skip_serializing_if = "Vec::is_empty", deserialize_with = "crate::serialization::c_strings")]
+    pub narration_characall: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fighter_jingles: Vec<FighterJingleManifest>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stream_property: Vec<StreamPropertyEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assigned_info: Vec<AssignedInfoEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stream_set: Vec<StreamSetEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bgm_db: Vec<BgmDatabaseRootEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sli: Vec<smash_sli::SliEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bgm_property: Vec<smash_bgm_property::BgmPropertyEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub playlists: Vec<PlaylistManifest>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stage_db: Vec<StageDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ui_stage_resources: Vec<UiStageResourcesManifest>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tips_db: Vec<TipsDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub amiibo_db: Vec<AmiiboDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mii_body_db: Vec<MiiBodyDatabaseEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mii_hat_db: Vec<MiiHatDatabaseEntry>,
+    /// The labels the manifest's hashes were written as, see [`Manifest::add_labels`].
+    #[serde(skip)]
+    pub labels: Vec<String>,
+}
+
+impl Manifest {
+    pub fn from_toml_str(text: &str) -> Result<Self, ManifestError> {
+        let (manifest, labels) = crate::serialization::collect_labels(|| toml::from_str::<Self>(text));
+        Ok(Manifest { labels, ..manifest.map_err(ManifestError::Toml)? })
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self, ManifestError> {
+        let (manifest, labels) = crate::serialization::collect_labels(|| serde_json::from_str::<Self>(text));
+        Ok(Manifest { labels, ..manifest.map_err(ManifestError::Json)? })
+    }
+
+    /// Reads a manifest, picking the format from the file extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let read = || std::fs::read_to_string(path).map_err(|err| ManifestError::Io(path.to_path_buf(), err));
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&read()?),
+            Some("json") => Self::from_json_str(&read()?),
+            _ => Err(ManifestError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    pub fn to_json_string(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Adds the labels the manifest was read with, so its hashes print and serialize back as those labels.
+    pub fn add_labels(&self) {
+        for label in self.labels.iter() {
+            crate::hash40::add_label(label);
+        }
+    }
+
+    /// Adds the manifest's labels, then registers every entry as a [`Batch`], so nothing is registered if any of
+    /// them is invalid, conflicts or refers to something missing.
+    pub fn register(&self) -> Result<(), BatchError> {
+        self.add_labels();
+        Batch::from(self).commit()
+    }
+}
+
+/// Reads the manifest at `path` and registers everything in it.
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+    let manifest = Manifest::from_file(path)?;
+    manifest.register().map_err(ManifestError::Batch)?;
+    Ok(manifest)
+}
@@ -0,0 +1,377 @@
+//! Reading and writing `.nus3audio` archives, the files a nus3bank's streamed tones are played from.
+//!
+//! An archive is a `NUS3` header, an `AUDIINDX` with the file count, then one section per column:
+//! `TNID` the file ids, `NMOF` where each name is in `TNNM`, `ADOF` where each file is in `PACK`.
+//! Every offset is from the start of the archive. `JUNK` pads `PACK` so every file in it starts
+//! 16 byte aligned, which [`Nus3audio::to_bytes`] keeps no matter what was added or removed.
+//!
+//! A file's id is the id of the tone in the companion nus3bank that plays it, so tones should be
+//! added to both at once with [`append_tones_with_audio`].
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::nus3bank::{Nus3bank, Nus3bankError, ToneEntry};
+
+// Where PACK and every file in it start
+const ALIGNMENT: usize = 0x10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nus3audioError {
+    BadMagic { expected: Vec<u8>, found: Vec<u8> },
+    /// One of the sections every archive has isn't there.
+    MissingSection([u8; 4]),
+    /// The data ended at `offset` before everything was read.
+    Truncated { offset: u64 },
+    /// A section's size doesn't match the file count.
+    SizeMismatch { expected: u64, found: u64 },
+    /// A name at `offset` isn't NUL terminated or isn't valid UTF-8.
+    InvalidName { offset: u64 },
+    FileNotFound(String),
+    /// Adding or renaming would leave two files with the same name.
+    DuplicateFile(String),
+    /// A section is in the archive more than once.
+    DuplicateSection([u8; 4]),
+    /// A file already has the id `u32::MAX`, so there's none after it to add a file with.
+    IdOverflow,
+}
+
+impl fmt::Display for Nus3audioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Nus3audioError::BadMagic { expected, found } => write!(
+                f,
+                "Your nus3audio magic does not equal to {}! Read: {}",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(found)
+            ),
+            Nus3audioError::MissingSection(magic) => {
+                write!(f, "Did not read {}! Your nus3audio file may be malformed.", String::from_utf8_lossy(magic))
+            }
+            Nus3audioError::Truncated { offset } => write!(f, "Your nus3audio ends too early at {:#x}!", offset),
+            Nus3audioError::SizeMismatch { expected, found } => {
+                write!(f, "Size mismatch in your nus3audio! Expected: {:#x}, Read: {:#x}", expected, found)
+            }
+            Nus3audioError::InvalidName { offset } => write!(f, "Failed reading the name at {:#x}!", offset),
+            Nus3audioError::FileNotFound(name) => write!(f, "Could not find the file {} in your nus3audio!", name),
+            Nus3audioError::DuplicateFile(name) => write!(f, "Your nus3audio already has a file named {}!", name),
+            Nus3audioError::DuplicateSection(magic) => {
+                write!(f, "Read {} twice! Your nus3audio file may be malformed.", String::from_utf8_lossy(magic))
+            }
+            Nus3audioError::IdOverflow => write!(f, "Your nus3audio has no file id left to add a file with!"),
+        }
+    }
+}
+
+impl std::error::Error for Nus3audioError {}
+
+fn bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], Nus3audioError> {
+    offset
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .map(|end| &data[offset..end])
+        .ok_or(Nus3audioError::Truncated { offset: offset as u64 })
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, Nus3audioError> {
+    Ok(u32::from_le_bytes(bytes(data, offset, 4)?.try_into().unwrap()))
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn align(len: usize) -> usize {
+    len.next_multiple_of(ALIGNMENT)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioFile {
+    pub id: u32,
+    pub name: String,
+    /// Usually an `.lopus` or `.idsp` stream.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Nus3audio {
+    pub files: Vec<AudioFile>,
+}
+
+impl Nus3audio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, Nus3audioError> {
+        let magic = bytes(data, 0, 4)?;
+        if magic != b"NUS3" {
+            return Err(Nus3audioError::BadMagic { expected: b"NUS3".to_vec(), found: magic.to_vec() });
+        }
+        let index = bytes(data, 8, 8)?;
+        if index != b"AUDIINDX" {
+            return Err(Nus3audioError::BadMagic { expected: b"AUDIINDX".to_vec(), found: index.to_vec() });
+        }
+        let index_size = u32_at(data, 16)? as usize;
+        let count = u32_at(data, 20)? as usize;
+
+        // Where every section's data starts, and how long it is
+        let mut sections = HashMap::new();
+        let mut pos = 20 + index_size;
+        while pos < data.len() {
+            let magic: [u8; 4] = bytes(data, pos, 4)?.try_into().unwrap();
+            let size = u32_at(data, pos + 4)? as usize;
+            bytes(data, pos + 8, size)?;
+            if sections.insert(magic, (pos + 8, size)).is_some() {
+                return Err(Nus3audioError::DuplicateSection(magic));
+            }
+            pos += 8 + size;
+        }
+        let section = |magic: &[u8; 4], entry_size: usize| {
+            let (start, size) = *sections.get(magic).ok_or(Nus3audioError::MissingSection(*magic))?;
+            if size < count * entry_size {
+                return Err(Nus3audioError::SizeMismatch { expected: (count * entry_size) as u64, found: size as u64 });
+            }
+            Ok(start)
+        };
+        let ids = section(b"TNID", 4)?;
+        let name_offsets = section(b"NMOF", 4)?;
+        let file_offsets = section(b"ADOF", 8)?;
+
+        let mut files = Vec::with_capacity(count.min(data.len() / 16));
+        for index in 0..count {
+            let id = u32_at(data, ids + index * 4)?;
+            let name_offset = u32_at(data, name_offsets + index * 4)? as usize;
+            let name_len = data
+                .get(name_offset..)
+                .and_then(|name| name.iter().position(|byte| *byte == 0))
+                .ok_or(Nus3audioError::InvalidName { offset: name_offset as u64 })?;
+            let name = std::str::from_utf8(&data[name_offset..name_offset + name_len])
+                .map_err(|_| Nus3audioError::InvalidName { offset: name_offset as u64 })?;
+            let offset = u32_at(data, file_offsets + index * 8)? as usize;
+            let size = u32_at(data, file_offsets + index * 8 + 4)? as usize;
+            // Empty files may have no offset at all
+            let file = if size == 0 { &[][..] } else { bytes(data, offset, size)? };
+            files.push(AudioFile { id, name: name.to_string(), data: file.to_vec() });
+        }
+        Ok(Nus3audio { files })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let count = self.files.len();
+        let mut names = Vec::new();
+        let mut name_offsets = Vec::with_capacity(count);
+        // NUS3 and AUDIINDX, then the 4 column sections
+        let tnnm_start = 0x18 + 8 + count * 4 + 8 + count * 4 + 8 + count * 8 + 8;
+        for file in self.files.iter() {
+            name_offsets.push((tnnm_start + names.len()) as u32);
+            names.extend_from_slice(file.name.as_bytes());
+            names.push(0);
+        }
+        // JUNK fills up to where PACK's data lines up, past both their headers
+        let junk_start = tnnm_start + names.len();
+        let pack_start = align(junk_start + 16);
+        let junk_size = pack_start - 16 - junk_start;
+
+        let mut pack = Vec::new();
+        let mut file_offsets = Vec::with_capacity(count);
+        for file in self.files.iter() {
+            file_offsets.push(((pack_start + pack.len()) as u32, file.data.len() as u32));
+            pack.extend_from_slice(&file.data);
+            pack.resize(align(pack.len()), 0);
+        }
+
+        let mut out = Vec::with_capacity(pack_start + pack.len());
+        out.extend_from_slice(b"NUS3");
+        push_u32(&mut out, 0);
+        out.extend_from_slice(b"AUDIINDX");
+        push_u32(&mut out, 4);
+        push_u32(&mut out, count as u32);
+        out.extend_from_slice(b"TNID");
+        push_u32(&mut out, (count * 4) as u32);
+        for file in self.files.iter() {
+            push_u32(&mut out, file.id);
+        }
+        out.extend_from_slice(b"NMOF");
+        push_u32(&mut out, (count * 4) as u32);
+        for offset in name_offsets {
+            push_u32(&mut out, offset);
+        }
+        out.extend_from_slice(b"ADOF");
+        push_u32(&mut out, (count * 8) as u32);
+        for (offset, size) in file_offsets {
+            push_u32(&mut out, offset);
+            push_u32(&mut out, size);
+        }
+        out.extend_from_slice(b"TNNM");
+        push_u32(&mut out, names.len() as u32);
+        out.extend_from_slice(&names);
+        out.extend_from_slice(b"JUNK");
+        push_u32(&mut out, junk_size as u32);
+        out.resize(out.len() + junk_size, 0);
+        out.extend_from_slice(b"PACK");
+        push_u32(&mut out, pack.len() as u32);
+        out.extend_from_slice(&pack);
+
+        let size = (out.len() - 8) as u32;
+        out[4..8].copy_from_slice(&size.to_le_bytes());
+        out
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|file| file.name.as_str())
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.files.iter().position(|file| file.name == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AudioFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    pub fn get_by_id(&self, id: u32) -> Option<&AudioFile> {
+        self.files.iter().find(|file| file.id == id)
+    }
+
+    /// Adds a file after the others, with the id after the highest one. Returns the new id.
+    pub fn add(&mut self, name: &str, data: Vec<u8>) -> Result<u32, Nus3audioError> {
+        if self.position(name).is_some() {
+            return Err(Nus3audioError::DuplicateFile(name.to_string()));
+        }
+        let id = match self.files.iter().map(|file| file.id).max() {
+            Some(last) => last.checked_add(1).ok_or(Nus3audioError::IdOverflow)?,
+            None => 0,
+        };
+        self.files.push(AudioFile { id, name: name.to_string(), data });
+        Ok(id)
+    }
+
+    /// Swaps the data of the `name` file, keeping its id.
+    pub fn replace(&mut self, name: &str, data: Vec<u8>) -> Result<(), Nus3audioError> {
+        let index = self.position(name).ok_or_else(|| Nus3audioError::FileNotFound(name.to_string()))?;
+        self.files[index].data = data;
+        Ok(())
+    }
+
+    /// Removes the `name` file, leaving a nameless empty file with its id so no other id changes, like
+    /// [`Nus3bank::remove_tones`] does for the tone with that id.
+    pub fn remove(&mut self, name: &str) -> Result<AudioFile, Nus3audioError> {
+        let index = self.position(name).ok_or_else(|| Nus3audioError::FileNotFound(name.to_string()))?;
+        let placeholder = AudioFile { id: self.files[index].id, name: String::new(), data: Vec::new() };
+        Ok(std::mem::replace(&mut self.files[index], placeholder))
+    }
+
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<(), Nus3audioError> {
+        if name != new_name && self.position(new_name).is_some() {
+            return Err(Nus3audioError::DuplicateFile(new_name.to_string()));
+        }
+        let index = self.position(name).ok_or_else(|| Nus3audioError::FileNotFound(name.to_string()))?;
+        self.files[index].name = new_name.to_string();
+        Ok(())
+    }
+}
+
+/// A nus3bank and its nus3audio disagreeing, or either failing on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncError {
+    Bank(Nus3bankError),
+    Audio(Nus3audioError),
+    /// The tone at `id` in the bank and the audio file with that id aren't the same, `None` if either is missing.
+    OutOfSync { id: u32, bank: Option<String>, audio: Option<String> },
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Bank(err) => err.fmt(f),
+            SyncError::Audio(err) => err.fmt(f),
+            SyncError::OutOfSync { id, bank, audio } => write!(
+                f,
+                "Your nus3bank and nus3audio are out of sync at tone {}! nus3bank has {}, nus3audio has {}",
+                id,
+                bank.as_deref().unwrap_or("nothing"),
+                audio.as_deref().unwrap_or("nothing")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+impl From<Nus3bankError> for SyncError {
+    fn from(err: Nus3bankError) -> Self {
+        SyncError::Bank(err)
+    }
+}
+
+impl From<Nus3audioError> for SyncError {
+    fn from(err: Nus3audioError) -> Self {
+        SyncError::Audio(err)
+    }
+}
+
+/// Checks every named tone has the audio file with its id and name, and the other way around.
+/// Tones that couldn't be parsed (placeholders) aren't checked, they have no name to compare.
+pub fn check_sync(bank: &Nus3bank, audio: &Nus3audio) -> Result<(), SyncError> {
+    let tones = bank.tone_section().map_or(&[][..], |tones| &tones.tones[..]);
+    let out_of_sync = |id: usize| SyncError::OutOfSync {
+        id: id as u32,
+        bank: tones.get(id).and_then(|tone| tone.name()).map(str::to_string),
+        audio: audio.get_by_id(id as u32).map(|file| file.name.clone()),
+    };
+    for (id, tone) in tones.iter().enumerate() {
+        if let ToneEntry::Named(tone) = tone {
+            if !audio.get_by_id(id as u32).is_some_and(|file| file.name == tone.name) {
+                return Err(out_of_sync(id));
+            }
+        }
+    }
+    for file in audio.files.iter() {
+        let id = file.id as usize;
+        if !tones.get(id).is_some_and(|tone| !matches!(tone, ToneEntry::Named(tone) if tone.name != file.name)) {
+            return Err(out_of_sync(id));
+        }
+    }
+    Ok(())
+}
+
+/// Adds a tone to the bank and a file to the audio for every `(name, data)`, with the tone's metadata
+/// copied from `source_name` like [`Nus3bank::append_tones`] and the file's id the new tone's. The two
+/// have to be in sync to begin with, and nothing changes when anything fails.
+pub fn append_tones_with_audio(
+    bank: &mut Nus3bank,
+    audio: &mut Nus3audio,
+    source_name: &str,
+    tones: &[(String, Vec<u8>)],
+) -> Result<(), SyncError> {
+    check_sync(bank, audio)?;
+    for (index, (name, _)) in tones.iter().enumerate() {
+        let repeated = tones[..index].iter().any(|(other, _)| other == name);
+        if repeated || bank.tone_section().is_some_and(|section| section.position(name).is_some()) {
+            return Err(Nus3bankError::DuplicateTone(name.clone()).into());
+        }
+        if audio.position(name).is_some() {
+            return Err(Nus3audioError::DuplicateFile(name.clone()).into());
+        }
+    }
+    let first_id = bank.tone_section().map_or(0, |section| section.tones.len());
+    let names: Vec<String> = tones.iter().map(|(name, _)| name.clone()).collect();
+    bank.append_tones(source_name, &names)?;
+    for (index, (name, data)) in tones.iter().enumerate() {
+        audio.files.push(AudioFile { id: (first_id + index) as u32, name: name.clone(), data: data.clone() });
+    }
+    Ok(())
+}
+
+/// [`append_tones_with_audio`] on the bytes of both files, returning the new nus3bank and nus3audio.
+pub fn append_entries_to_nus3bank_and_nus3audio(
+    bank: &[u8],
+    audio: &[u8],
+    source_name: &str,
+    tones: &[(String, Vec<u8>)],
+) -> Result<(Vec<u8>, Vec<u8>), SyncError> {
+    let mut bank = Nus3bank::parse(bank)?;
+    let mut audio = Nus3audio::parse(audio)?;
+    append_tones_with_audio(&mut bank, &mut audio, source_name, tones)?;
+    Ok((bank.to_bytes()?, audio.to_bytes()))
+}
@@ -0,0 +1,721 @@
+//! Structured reading and writing of `.nus3bank` files.
+//!
+//! A bank is a `NUS3` header, a `BANKTOC ` listing every section's magic and size, then the sections
+//! themselves in the same order. Sections whose layout isn't understood well enough to be rebuilt
+//! byte for byte are kept as [`Section::Unknown`], so an unmodified bank always writes back identical.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::ops::Range;
+
+// Marks the start of a tone's sub metadata
+const SUB_META_MAGIC: u32 = 0x22E8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nus3bankError {
+    /// A magic (`NUS3` or a section's) isn't what it should be.
+    BadMagic { expected: [u8; 4], found: [u8; 4] },
+    /// The `BANKTOC ` doesn't follow the `NUS3` header.
+    MissingToc,
+    /// The tone to remove, rename or change doesn't exist.
+    ToneNotFound(String),
+    /// The tone to copy from doesn't exist.
+    SourceToneMissing(String),
+    /// The data ended at `offset` before everything was read. For metadata lookups on a
+    /// single tone the offset is relative to the start of that tone's body.
+    Truncated { offset: u64 },
+    /// A size or offset doesn't match what the surrounding data says it should be.
+    SizeMismatch { expected: u64, found: u64 },
+    /// Tone names are stored with a length byte, so they can be 254 bytes at most.
+    NameTooLong(String),
+    /// A name at `offset` isn't NUL terminated or isn't valid UTF-8.
+    InvalidName { offset: u64 },
+    /// Renaming would leave two tones with the same name.
+    DuplicateTone(String),
+    /// The tone has no `0x22E8` marker starting its sub metadata.
+    MissingSubMeta(String),
+    /// The section reads fine but wouldn't be written back byte for byte, so it's kept as [`Section::Unknown`]
+    /// and can't be edited.
+    UnsupportedSection([u8; 4]),
+}
+
+impl fmt::Display for Nus3bankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Nus3bankError::BadMagic { expected, found } => write!(
+                f,
+                "Your nus3bank magic does not equal to {}! Read: {}",
+                String::from_utf8_lossy(expected),
+                String::from_utf8_lossy(found)
+            ),
+            Nus3bankError::MissingToc => write!(f, "Did not read BANKTOC! Your nus3bank file may be malformed."),
+            Nus3bankError::ToneNotFound(name) => write!(f, "Could not find the tone {} in your nus3bank!", name),
+            Nus3bankError::SourceToneMissing(name) => {
+                write!(f, "Could not find the source tone {} in your nus3bank!", name)
+            }
+            Nus3bankError::Truncated { offset } => write!(f, "Your nus3bank ends too early at {:#x}!", offset),
+            Nus3bankError::SizeMismatch { expected, found } => {
+                write!(f, "Size mismatch in your nus3bank! Expected: {:#x}, Read: {:#x}", expected, found)
+            }
+            Nus3bankError::NameTooLong(name) => write!(f, "The tone name {} is too long!", name),
+            Nus3bankError::InvalidName { offset } => write!(f, "Failed reading the name at {:#x}!", offset),
+            Nus3bankError::DuplicateTone(name) => write!(f, "Your nus3bank already has a tone named {}!", name),
+            Nus3bankError::MissingSubMeta(name) => write!(f, "Could not find the sub metadata of the tone {}!", name),
+            Nus3bankError::UnsupportedSection(magic) => write!(
+                f,
+                "The {} section of your nus3bank can't be rebuilt, so it can't be edited!",
+                String::from_utf8_lossy(magic)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Nus3bankError {}
+
+pub fn read_u32(cursor: &mut Cursor<&mut [u8]>) -> Result<u32, Nus3bankError> {
+    let offset = cursor.position();
+    let buf: &mut [u8; 4] = &mut [0; 4];
+    cursor.read_exact(buf).map_err(|_| Nus3bankError::Truncated { offset })?;
+    Ok(u32::from_le_bytes(*buf))
+}
+
+pub fn get_sub_meta_offset_and_size(cursor: &mut Cursor<&mut [u8]>) -> Result<(u64, u64), Nus3bankError> {
+    // Source Data offset, Source Data Size
+    while cursor.position() % 4 != 0 {
+        cursor.set_position(cursor.position() + 1);
+    }
+
+    while read_u32(cursor)? != SUB_META_MAGIC {}
+
+    let start_pos = cursor.position();
+    let mut break_counter = 0;
+    loop {
+        let val = read_u32(cursor)?;
+        if break_counter % 2 == 0 {
+            if val == 0 {
+                break_counter += 1;
+            } else {
+                break_counter = 0;
+            }
+        } else if val == 0xFFFFFFFF {
+            break_counter += 1;
+        } else {
+            break_counter = 0;
+        }
+
+        if break_counter == 8 {
+            break;
+        }
+    }
+
+    Ok((start_pos, cursor.position() - start_pos))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Nus3bankError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(Nus3bankError::Truncated { offset: self.pos as u64 })?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn magic<const N: usize>(&mut self) -> Result<[u8; N], Nus3bankError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, Nus3bankError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Nus3bankError> {
+        Ok(u32::from_le_bytes(self.magic()?))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.pos..];
+        self.pos = self.data.len();
+        rest
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+// Names are stored as a length byte (including the NUL) followed by the NUL terminated string,
+// zero padded so the whole thing ends 4 byte aligned relative to `start`
+fn push_name(out: &mut Vec<u8>, start: usize, name: &str) -> Result<(), Nus3bankError> {
+    if name.len() >= u8::MAX as usize {
+        return Err(Nus3bankError::NameTooLong(name.to_string()));
+    }
+    out.push(name.len() as u8 + 1);
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    while (out.len() - start) % 4 != 0 {
+        out.push(0);
+    }
+    Ok(())
+}
+
+fn read_name(reader: &mut Reader, start: usize) -> Result<String, Nus3bankError> {
+    let offset = reader.pos as u64;
+    let len = reader.u8()? as usize;
+    if len == 0 {
+        return Err(Nus3bankError::InvalidName { offset });
+    }
+    let name = reader.bytes(len)?;
+    if name[len - 1] != 0 {
+        return Err(Nus3bankError::InvalidName { offset });
+    }
+    while (reader.pos - start) % 4 != 0 {
+        reader.u8()?;
+    }
+    String::from_utf8(name[..len - 1].to_vec()).map_err(|_| Nus3bankError::InvalidName { offset })
+}
+
+// The entries, the offsets stored for empty entries by index, and whatever follows the last entry
+type Table<'a> = (Vec<&'a [u8]>, BTreeMap<usize, u32>, &'a [u8]);
+
+// Sections like TONE hold a count, an (offset, size) table, then the entries back to back.
+// Empty entries can store any offset, so theirs are kept to be written back as they were.
+fn read_table(data: &[u8]) -> Result<Table<'_>, Nus3bankError> {
+    let mut reader = Reader::new(data);
+    let count = reader.u32()? as usize;
+    let mut entries = Vec::with_capacity(count.min(data.len() / 8));
+    let mut expected = 4 + count * 8;
+    let mut ranges = Vec::new();
+    let mut empty_offsets = BTreeMap::new();
+    for index in 0..count {
+        let offset = reader.u32()? as usize;
+        let size = reader.u32()? as usize;
+        if size == 0 {
+            empty_offsets.insert(index, offset as u32);
+        } else if offset != expected {
+            return Err(Nus3bankError::SizeMismatch { expected: expected as u64, found: offset as u64 });
+        }
+        ranges.push((expected, size));
+        expected += size;
+    }
+    for (offset, size) in ranges {
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= data.len())
+            .ok_or(Nus3bankError::Truncated { offset: data.len() as u64 })?;
+        entries.push(&data[offset..end]);
+    }
+    Ok((entries, empty_offsets, &data[expected.min(data.len())..]))
+}
+
+// Empty entries without a kept offset get the one the next entry starts at
+fn write_table(out: &mut Vec<u8>, entries: &[Vec<u8>], empty_offsets: &BTreeMap<usize, u32>, trailing: &[u8]) {
+    let start = out.len();
+    push_u32(out, entries.len() as u32);
+    let table = out.len();
+    out.resize(table + entries.len() * 8, 0);
+    for (index, entry) in entries.iter().enumerate() {
+        let offset = match empty_offsets.get(&index) {
+            Some(offset) if entry.is_empty() => *offset,
+            _ => (out.len() - start) as u32,
+        };
+        let size = entry.len() as u32;
+        out[table + index * 8..table + index * 8 + 4].copy_from_slice(&offset.to_le_bytes());
+        out[table + index * 8 + 4..table + index * 8 + 8].copy_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(entry);
+    }
+    out.extend_from_slice(trailing);
+}
+
+/// Project properties. The layout isn't documented, so the data is kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prop {
+    pub data: Vec<u8>,
+}
+
+/// Bank info, holding the bank's name and the id `soundlabelinfo.sli` refers to it with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binf {
+    pub unk0: u32,
+    pub flags: u32,
+    pub name: String,
+    pub id: u32,
+}
+
+impl Binf {
+    fn parse(data: &[u8]) -> Result<Self, Nus3bankError> {
+        let mut reader = Reader::new(data);
+        let unk0 = reader.u32()?;
+        let flags = reader.u32()?;
+        let name = read_name(&mut reader, 0)?;
+        let id = reader.u32()?;
+        Ok(Binf { unk0, flags, name, id })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), Nus3bankError> {
+        let start = out.len();
+        push_u32(out, self.unk0);
+        push_u32(out, self.flags);
+        push_name(out, start, &self.name)?;
+        push_u32(out, self.id);
+        Ok(())
+    }
+}
+
+/// Tone groups, kept as raw entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grp {
+    pub entries: Vec<Vec<u8>>,
+    /// The offsets the empty entries were stored with, by index.
+    pub empty_offsets: BTreeMap<usize, u32>,
+    pub trailing: Vec<u8>,
+}
+
+/// Tone dynamics/settings, kept as raw entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dton {
+    pub entries: Vec<Vec<u8>>,
+    /// The offsets the empty entries were stored with, by index.
+    pub empty_offsets: BTreeMap<usize, u32>,
+    pub trailing: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tone {
+    pub header: [u8; 12],
+    pub name: String,
+    // Everything after the padded name, starting with the stream offset/size block and the sub metadata
+    pub body: Vec<u8>,
+}
+
+impl Tone {
+    fn parse(data: &[u8]) -> Result<Self, Nus3bankError> {
+        let mut reader = Reader::new(data);
+        let header = reader.magic()?;
+        let name = read_name(&mut reader, 0)?;
+        Ok(Tone { header, name, body: reader.rest().to_vec() })
+    }
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<(), Nus3bankError> {
+        let start = out.len();
+        out.extend_from_slice(&self.header);
+        push_name(out, start, &self.name)?;
+        out.extend_from_slice(&self.body);
+        Ok(())
+    }
+
+    /// The sub metadata: everything after the `0x22E8` marker up to and including the
+    /// four `(0, 0xFFFFFFFF)` pairs closing it.
+    pub fn sub_meta(&self) -> Result<&[u8], Nus3bankError> {
+        self.sub_meta_range().map(|range| &self.body[range])
+    }
+
+    // Where the sub metadata sits in `body`
+    fn sub_meta_range(&self) -> Result<Range<usize>, Nus3bankError> {
+        let words: Vec<u32> = self.body.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect();
+        let start = words
+            .iter()
+            .position(|word| *word == SUB_META_MAGIC)
+            .ok_or_else(|| Nus3bankError::MissingSubMeta(self.name.clone()))?
+            + 1;
+        let mut break_counter = 0;
+        for (index, word) in words.iter().enumerate().skip(start) {
+            let expected = if break_counter % 2 == 0 { 0 } else { 0xFFFFFFFF };
+            break_counter = if *word == expected { break_counter + 1 } else { 0 };
+            if break_counter == 8 {
+                return Ok(start * 4..(index + 1) * 4);
+            }
+        }
+        Err(Nus3bankError::Truncated { offset: self.body.len() as u64 })
+    }
+
+    /// Swaps this tone's sub metadata for `meta`, keeping everything around it.
+    pub fn set_sub_meta(&mut self, meta: &[u8]) -> Result<(), Nus3bankError> {
+        let range = self.sub_meta_range()?;
+        self.body.splice(range, meta.iter().copied());
+        Ok(())
+    }
+
+    /// A copy of this tone under a new name, the same way `append_entries_to_nus3bank` always built them.
+    pub fn clone_as(&self, name: &str) -> Result<Tone, Nus3bankError> {
+        if name.len() >= u8::MAX as usize {
+            return Err(Nus3bankError::NameTooLong(name.to_string()));
+        }
+        let meta = self.sub_meta()?;
+        let mut body = Vec::with_capacity(16 + meta.len());
+        for value in [0, 8, 0, SUB_META_MAGIC] {
+            push_u32(&mut body, value);
+        }
+        body.extend_from_slice(meta);
+        Ok(Tone { header: self.header, name: name.to_string(), body })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToneEntry {
+    Named(Tone),
+    // Placeholder or otherwise unparsable tones, kept so the tone ids after them stay the same
+    Raw(Vec<u8>),
+}
+
+impl ToneEntry {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ToneEntry::Named(tone) => Some(&tone.name),
+            ToneEntry::Raw(_) => None,
+        }
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Nus3bankError> {
+        match self {
+            ToneEntry::Named(tone) => {
+                let mut out = Vec::new();
+                tone.write(&mut out)?;
+                Ok(out)
+            }
+            ToneEntry::Raw(data) => Ok(data.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToneSection {
+    pub tones: Vec<ToneEntry>,
+    /// The offsets the empty tones were stored with, by tone id. Tones emptied since get the next tone's offset.
+    pub empty_offsets: BTreeMap<usize, u32>,
+    pub trailing: Vec<u8>,
+}
+
+impl ToneSection {
+    fn parse(data: &[u8]) -> Result<Self, Nus3bankError> {
+        let (entries, empty_offsets, trailing) = read_table(data)?;
+        let tones = entries
+            .into_iter()
+            .map(|entry| match Tone::parse(entry) {
+                Ok(tone) if ToneEntry::Named(tone.clone()).to_bytes().as_deref() == Ok(entry) => ToneEntry::Named(tone),
+                _ => ToneEntry::Raw(entry.to_vec()),
+            })
+            .collect();
+        Ok(ToneSection { tones, empty_offsets, trailing: trailing.to_vec() })
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.tones.iter().position(|tone| tone.name() == Some(name))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tone> {
+        self.tones.iter().find_map(|tone| match tone {
+            ToneEntry::Named(tone) if tone.name == name => Some(tone),
+            _ => None,
+        })
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tone> {
+        self.tones.iter_mut().find_map(|tone| match tone {
+            ToneEntry::Named(tone) if tone.name == name => Some(tone),
+            _ => None,
+        })
+    }
+}
+
+/// Padding section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Junk {
+    pub data: Vec<u8>,
+}
+
+/// Embedded stream data the tones point into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Section {
+    Prop(Prop),
+    Binf(Binf),
+    Grp(Grp),
+    Dton(Dton),
+    Tone(ToneSection),
+    Junk(Junk),
+    Pack(Pack),
+    Unknown { magic: [u8; 4], data: Vec<u8> },
+}
+
+impl Section {
+    fn parse(magic: [u8; 4], data: &[u8]) -> Result<Section, Nus3bankError> {
+        let section = match &magic {
+            b"PROP" => Section::Prop(Prop { data: data.to_vec() }),
+            b"BINF" => Section::Binf(Binf::parse(data)?),
+            b"GRP " => {
+                let (entries, empty_offsets, trailing) = read_table(data)?;
+                Section::Grp(Grp {
+                    entries: entries.into_iter().map(|entry| entry.to_vec()).collect(),
+                    empty_offsets,
+                    trailing: trailing.to_vec(),
+                })
+            }
+            b"DTON" => {
+                let (entries, empty_offsets, trailing) = read_table(data)?;
+                Section::Dton(Dton {
+                    entries: entries.into_iter().map(|entry| entry.to_vec()).collect(),
+                    empty_offsets,
+                    trailing: trailing.to_vec(),
+                })
+            }
+            b"TONE" => Section::Tone(ToneSection::parse(data)?),
+            b"JUNK" => Section::Junk(Junk { data: data.to_vec() }),
+            b"PACK" => Section::Pack(Pack { data: data.to_vec() }),
+            _ => return Ok(Section::Unknown { magic, data: data.to_vec() }),
+        };
+        // Sections that read fine but wouldn't be written back the same (e.g. unusual padding) are kept as is,
+        // editing them is refused with UnsupportedSection
+        if section.data().as_deref() == Ok(data) {
+            Ok(section)
+        } else {
+            Ok(Section::Unknown { magic, data: data.to_vec() })
+        }
+    }
+
+    pub fn magic(&self) -> [u8; 4] {
+        match self {
+            Section::Prop(_) => *b"PROP",
+            Section::Binf(_) => *b"BINF",
+            Section::Grp(_) => *b"GRP ",
+            Section::Dton(_) => *b"DTON",
+            Section::Tone(_) => *b"TONE",
+            Section::Junk(_) => *b"JUNK",
+            Section::Pack(_) => *b"PACK",
+            Section::Unknown { magic, .. } => *magic,
+        }
+    }
+
+    /// The section contents, without the magic and size. Fails if a name is too long for its length byte.
+    pub fn data(&self) -> Result<Vec<u8>, Nus3bankError> {
+        let mut out = Vec::new();
+        match self {
+            Section::Prop(Prop { data }) | Section::Junk(Junk { data }) | Section::Pack(Pack { data }) => {
+                out.extend_from_slice(data)
+            }
+            Section::Unknown { data, .. } => out.extend_from_slice(data),
+            Section::Binf(binf) => binf.write(&mut out)?,
+            Section::Grp(Grp { entries, empty_offsets, trailing })
+            | Section::Dton(Dton { entries, empty_offsets, trailing }) => {
+                write_table(&mut out, entries, empty_offsets, trailing)
+            }
+            Section::Tone(tone) => {
+                let entries = tone.tones.iter().map(ToneEntry::to_bytes).collect::<Result<Vec<_>, _>>()?;
+                write_table(&mut out, &entries, &tone.empty_offsets, &tone.trailing)
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nus3bank {
+    pub sections: Vec<Section>,
+    // Anything after the last section listed in the BANKTOC
+    pub trailing: Vec<u8>,
+}
+
+impl Nus3bank {
+    pub fn parse(data: &[u8]) -> Result<Self, Nus3bankError> {
+        let mut reader = Reader::new(data);
+        let magic: [u8; 4] = reader.magic()?;
+        if &magic != b"NUS3" {
+            return Err(Nus3bankError::BadMagic { expected: *b"NUS3", found: magic });
+        }
+        let bank_size = reader.u32()?;
+        let toc: [u8; 8] = reader.magic()?;
+        if &toc != b"BANKTOC " {
+            return Err(Nus3bankError::MissingToc);
+        }
+        let toc_size = reader.u32()? as u64;
+        let count = reader.u32()?;
+        if toc_size != 4 + count as u64 * 8 {
+            return Err(Nus3bankError::SizeMismatch { expected: 4 + count as u64 * 8, found: toc_size });
+        }
+        let mut toc_entries = Vec::with_capacity((count as usize).min(data.len() / 8));
+        for _ in 0..count {
+            toc_entries.push((reader.magic::<4>()?, reader.u32()?));
+        }
+
+        let mut sections = Vec::with_capacity(toc_entries.len());
+        for (magic, size) in toc_entries {
+            let section_magic: [u8; 4] = reader.magic()?;
+            if section_magic != magic {
+                return Err(Nus3bankError::BadMagic { expected: magic, found: section_magic });
+            }
+            let section_size = reader.u32()?;
+            if section_size != size {
+                return Err(Nus3bankError::SizeMismatch { expected: size as u64, found: section_size as u64 });
+            }
+            sections.push(Section::parse(magic, reader.bytes(size as usize)?)?);
+        }
+        // The header size covers everything after it, trailing data included
+        if bank_size as u64 != data.len() as u64 - 8 {
+            return Err(Nus3bankError::SizeMismatch { expected: data.len() as u64 - 8, found: bank_size as u64 });
+        }
+
+        Ok(Nus3bank { sections, trailing: reader.rest().to_vec() })
+    }
+
+    /// Fails with [`Nus3bankError::NameTooLong`] if a tone or the bank was given a name of 255 bytes or more.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Nus3bankError> {
+        let sections: Vec<([u8; 4], Vec<u8>)> = self
+            .sections
+            .iter()
+            .map(|section| Ok((section.magic(), section.data()?)))
+            .collect::<Result<_, Nus3bankError>>()?;
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"NUS3");
+        push_u32(&mut out, 0);
+        out.extend_from_slice(b"BANKTOC ");
+        push_u32(&mut out, 4 + sections.len() as u32 * 8);
+        push_u32(&mut out, sections.len() as u32);
+        for (magic, data) in sections.iter() {
+            out.extend_from_slice(magic);
+            push_u32(&mut out, data.len() as u32);
+        }
+        for (magic, data) in sections.iter() {
+            out.extend_from_slice(magic);
+            push_u32(&mut out, data.len() as u32);
+            out.extend_from_slice(data);
+        }
+        out.extend_from_slice(&self.trailing);
+
+        let size = (out.len() - 8) as u32;
+        out[4..8].copy_from_slice(&size.to_le_bytes());
+        Ok(out)
+    }
+
+    pub fn tone_section(&self) -> Option<&ToneSection> {
+        self.sections.iter().find_map(|section| match section {
+            Section::Tone(tone) => Some(tone),
+            _ => None,
+        })
+    }
+
+    pub fn tone_section_mut(&mut self) -> Option<&mut ToneSection> {
+        self.sections.iter_mut().find_map(|section| match section {
+            Section::Tone(tone) => Some(tone),
+            _ => None,
+        })
+    }
+
+    // The tone section to edit, `missing` if there's none and UnsupportedSection if it was kept as is
+    fn editable_tones(&mut self, missing: Nus3bankError) -> Result<&mut ToneSection, Nus3bankError> {
+        if self.sections.iter().any(|section| matches!(section, Section::Unknown { magic, .. } if magic == b"TONE")) {
+            return Err(Nus3bankError::UnsupportedSection(*b"TONE"));
+        }
+        self.tone_section_mut().ok_or(missing)
+    }
+
+    pub fn binf(&self) -> Option<&Binf> {
+        self.sections.iter().find_map(|section| match section {
+            Section::Binf(binf) => Some(binf),
+            _ => None,
+        })
+    }
+
+    /// Appends a copy of the `source_name` tone for every name in `new_entries`.
+    pub fn append_tones(&mut self, source_name: &str, new_entries: &[String]) -> Result<(), Nus3bankError> {
+        let tones = self.editable_tones(Nus3bankError::SourceToneMissing(source_name.to_string()))?;
+        let source = tones.get(source_name).ok_or_else(|| Nus3bankError::SourceToneMissing(source_name.to_string()))?;
+        let new_tones = new_entries
+            .iter()
+            .map(|name| source.clone_as(name).map(ToneEntry::Named))
+            .collect::<Result<Vec<_>, _>>()?;
+        tones.tones.extend(new_tones);
+        Ok(())
+    }
+
+    /// Removes every tone named in `names`.
+    /// Tone ids are positions in the tone section, which GRP, DTON, `soundlabelinfo.sli` and the paired nus3audio
+    /// all refer to, so each removed tone leaves an empty entry behind and the tones after it keep their ids.
+    pub fn remove_tones(&mut self, names: &[String]) -> Result<(), Nus3bankError> {
+        let Some(first) = names.first() else {
+            return Ok(());
+        };
+        let tones = self.editable_tones(Nus3bankError::ToneNotFound(first.clone()))?;
+        if let Some(name) = names.iter().find(|name| tones.position(name).is_none()) {
+            return Err(Nus3bankError::ToneNotFound(name.clone()));
+        }
+        for tone in tones.tones.iter_mut() {
+            if tone.name().is_some_and(|name| names.iter().any(|remove| remove == name)) {
+                *tone = ToneEntry::Raw(Vec::new());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rename_tone(&mut self, name: &str, new_name: &str) -> Result<(), Nus3bankError> {
+        if new_name.len() >= u8::MAX as usize {
+            return Err(Nus3bankError::NameTooLong(new_name.to_string()));
+        }
+        let tones = self.editable_tones(Nus3bankError::ToneNotFound(name.to_string()))?;
+        if name != new_name && tones.position(new_name).is_some() {
+            return Err(Nus3bankError::DuplicateTone(new_name.to_string()));
+        }
+        let tone = tones.get_mut(name).ok_or_else(|| Nus3bankError::ToneNotFound(name.to_string()))?;
+        tone.name = new_name.to_string();
+        Ok(())
+    }
+
+    /// Gives the `target_name` tone the sub metadata of the `source_name` tone.
+    pub fn replace_tone_meta(&mut self, target_name: &str, source_name: &str) -> Result<(), Nus3bankError> {
+        let tones = self.editable_tones(Nus3bankError::SourceToneMissing(source_name.to_string()))?;
+        let meta = tones
+            .get(source_name)
+            .ok_or_else(|| Nus3bankError::SourceToneMissing(source_name.to_string()))?
+            .sub_meta()?
+            .to_vec();
+        tones
+            .get_mut(target_name)
+            .ok_or_else(|| Nus3bankError::ToneNotFound(target_name.to_string()))?
+            .set_sub_meta(&meta)
+    }
+}
+
+// Keeps the &Vec it has always taken, so existing callers don't break
+#[allow(clippy::ptr_arg)]
+pub fn append_entries_to_nus3bank(
+    data: &mut [u8],
+    source_name: &str,
+    new_entries: &Vec<String>,
+) -> Result<Vec<u8>, Nus3bankError> {
+    let mut bank = Nus3bank::parse(data)?;
+    bank.append_tones(source_name, new_entries)?;
+    bank.to_bytes()
+}
+
+pub fn remove_entries_from_nus3bank(data: &[u8], names: &[String]) -> Result<Vec<u8>, Nus3bankError> {
+    let mut bank = Nus3bank::parse(data)?;
+    bank.remove_tones(names)?;
+    bank.to_bytes()
+}
+
+pub fn rename_entry_in_nus3bank(data: &[u8], name: &str, new_name: &str) -> Result<Vec<u8>, Nus3bankError> {
+    let mut bank = Nus3bank::parse(data)?;
+    bank.rename_tone(name, new_name)?;
+    bank.to_bytes()
+}
+
+pub fn replace_entry_meta_in_nus3bank(data: &[u8], target_name: &str, source_name: &str) -> Result<Vec<u8>, Nus3bankError> {
+    let mut bank = Nus3bank::parse(data)?;
+    bank.replace_tone_meta(target_name, source_name)?;
+    bank.to_bytes()
+}
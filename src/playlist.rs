@@ -0,0 +1,170 @@
+//! The 16 playlist slots of [`BgmPlaylistEntry`] as an array, and helpers keeping playlists in order.
+//!
+//! Every slot is one way the playlist can be played: `orderN` is the track's position in slot N and
+//! `incidenceN` how likely it is to be picked there, weighed against the other tracks' incidence.
+//!
+//! ```ignore
+//! let mut track = BgmPlaylistEntry::new("ui_bgm_custom");
+//! track.set_incidence(500);
+//! playlist::append_tracks_to_playlist(hash40("bgmmario").0, &vanilla_tracks, vec![track]);
+//! ```
+use crate::{BgmPlaylistEntry, ShortType, UnsignedShortType};
+
+pub const PLAYLIST_SLOTS: usize = 16;
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistSlot {
+    pub order: ShortType,
+    pub incidence: UnsignedShortType,
+}
+
+impl PlaylistSlot {
+    pub fn new(order: i16, incidence: u16) -> Self {
+        PlaylistSlot { order: ShortType::Overwrite(order), incidence: UnsignedShortType::Overwrite(incidence) }
+    }
+}
+
+/// [`BgmPlaylistEntry`] with its slots as an array.
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistTrack {
+    pub ui_bgm_id: u64,
+    pub slots: [PlaylistSlot; PLAYLIST_SLOTS],
+}
+
+impl From<BgmPlaylistEntry> for PlaylistTrack {
+    fn from(entry: BgmPlaylistEntry) -> Self {
+        PlaylistTrack { ui_bgm_id: entry.ui_bgm_id, slots: entry.slots() }
+    }
+}
+
+impl From<PlaylistTrack> for BgmPlaylistEntry {
+    fn from(track: PlaylistTrack) -> Self {
+        let mut entry = BgmPlaylistEntry { ui_bgm_id: track.ui_bgm_id, ..Default::default() };
+        entry.set_slots(track.slots);
+        entry
+    }
+}
+
+// `order field, incidence field;` for every slot
+macro_rules! playlist_slots {
+    ($($order:ident, $incidence:ident;)*) => {
+        impl BgmPlaylistEntry {
+            pub fn slots(&self) -> [PlaylistSlot; PLAYLIST_SLOTS] {
+                [$(PlaylistSlot { order: self.$order.clone(), incidence: self.$incidence.clone() }),*]
+            }
+
+            pub fn set_slots(&mut self, slots: [PlaylistSlot; PLAYLIST_SLOTS]) {
+                let [$($order),*] = slots.map(|slot| (slot.order, slot.incidence));
+                $(
+                    (self.$order, self.$incidence) = $order;
+                )*
+            }
+        }
+    };
+}
+
+playlist_slots! {
+    order0, incidence0;
+    order1, incidence1;
+    order2, incidence2;
+    order3, incidence3;
+    order4, incidence4;
+    order5, incidence5;
+    order6, incidence6;
+    order7, incidence7;
+    order8, incidence8;
+    order9, incidence9;
+    order10, incidence10;
+    order11, incidence11;
+    order12, incidence12;
+    order13, incidence13;
+    order14, incidence14;
+    order15, incidence15;
+}
+
+impl BgmPlaylistEntry {
+    /// The same incidence in every slot.
+    pub fn set_incidence(&mut self, incidence: u16) {
+        let mut slots = self.slots();
+        for slot in slots.iter_mut() {
+            slot.incidence = UnsignedShortType::Overwrite(incidence);
+        }
+        self.set_slots(slots);
+    }
+}
+
+/// The order after every track in `slot`, 0 for an empty slot. Unset and negative orders don't count.
+pub fn next_order(tracks: &[BgmPlaylistEntry], slot: usize) -> i16 {
+    tracks
+        .iter()
+        .filter_map(|track| track.slots()[slot].order.value().copied())
+        .filter(|order| *order >= 0)
+        .max()
+        .map_or(0, |order| order.saturating_add(1))
+}
+
+/// Gives every slot of `tracks` that has no order one after the end of `existing`, keeping their order.
+pub fn append_orders(existing: &[BgmPlaylistEntry], tracks: &mut [BgmPlaylistEntry]) {
+    for slot in 0..PLAYLIST_SLOTS {
+        let mut order = next_order(existing, slot).max(next_order(tracks, slot));
+        for track in tracks.iter_mut() {
+            let mut slots = track.slots();
+            if !slots[slot].order.is_set() {
+                slots[slot].order = ShortType::Overwrite(order);
+                order = order.saturating_add(1);
+                track.set_slots(slots);
+            }
+        }
+    }
+}
+
+/// The sum of the incidence set in `slot`.
+pub fn total_incidence(tracks: &[BgmPlaylistEntry], slot: usize) -> u32 {
+    tracks.iter().filter_map(|track| track.slots()[slot].incidence.value().copied()).map(u32::from).sum()
+}
+
+/// Scales down the incidence of every slot adding up to more than `max_total`, keeping their ratios. A track
+/// with any incidence keeps at least 1 so it can still be picked, which leaves a slot over `max_total` only if
+/// it has more of those tracks than that. With tracks already in the playlist, pass what's left of the total
+/// after [`total_incidence`].
+pub fn rescale_incidence(tracks: &mut [BgmPlaylistEntry], max_total: u32) {
+    for slot in 0..PLAYLIST_SLOTS {
+        let total = total_incidence(tracks, slot);
+        if total <= max_total {
+            continue;
+        }
+        // Rounded down so the total doesn't end up over
+        let mut scaled: Vec<Option<u32>> = tracks
+            .iter()
+            .map(|track| {
+                let incidence = *track.slots()[slot].incidence.value()? as u64;
+                let scaled = (incidence * max_total as u64 / total as u64) as u32;
+                Some(if incidence == 0 { 0 } else { scaled.max(1) })
+            })
+            .collect();
+        // Raising the smallest to 1 can go over, which comes off the largest
+        let mut excess = scaled.iter().flatten().sum::<u32>().saturating_sub(max_total);
+        while excess > 0 {
+            let Some(largest) = scaled.iter_mut().flatten().filter(|scaled| **scaled > 1).max_by_key(|scaled| **scaled)
+            else {
+                break;
+            };
+            *largest -= 1;
+            excess -= 1;
+        }
+        for (track, scaled) in tracks.iter_mut().zip(scaled) {
+            if let Some(scaled) = scaled {
+                let mut slots = track.slots();
+                slots[slot].incidence = UnsignedShortType::Overwrite(scaled as u16);
+                track.set_slots(slots);
+            }
+        }
+    }
+}
+
+/// Adds `tracks` after `existing`, the tracks the playlist already has, see [`append_orders`]. The plugin can't
+/// be asked for those, so they come from the game's `ui_bgm_db.prc` read with [`crate::convert::ParamEntry::from_row`].
+pub fn append_tracks_to_playlist(playlist: u64, existing: &[BgmPlaylistEntry], mut tracks: Vec<BgmPlaylistEntry>) {
+    append_orders(existing, &mut tracks);
+    crate::add_tracks_to_playlist(playlist, &tracks);
+}
@@ -0,0 +1,415 @@
+//! Keeps track of every id and slot handed to the `add_*` functions.
+//!
+//! The plugin takes whatever it's given and the last entry wins, so two mods using the same
+//! `save_no` or adding the same `ui_bgm_id` silently break each other. Every `add_*` call is
+//! recorded in a global [`Registry`], which lists the collisions in [`conflicts`] and hands out
+//! unused slots with [`next_free_save_no`] and [`next_free_disp_order`].
+//!
+//! A [`Registry`] can also be used on its own, e.g. to check several mods' manifests against each other.
+//!
+//! The registry doesn't know which ids and slots the base game uses until it's given the game's own params
+//! with [`record_params`]. After that a mod taking one of the slots conflicts with the vanilla entry holding it.
+//! The recorded rows are also what the crate's `get_*` and `iter_*` functions read back, see [`Registry::vanilla_entry`].
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::{Mutex, OnceLock};
+
+use crate::convert::{EntryKind, ParamEntry, ParamRow, ParamValue};
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, CharacterDatabaseEntry, CharacterLayoutDatabaseEntry,
+    GametitleDatabaseEntry, Hash40, MiiBodyDatabaseEntry, MiiHatDatabaseEntry, SeriesDatabaseEntry,
+    StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, TipsDatabaseEntry,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Table {
+    Chara,
+    CharaLayout,
+    Series,
+    Bgm,
+    StreamSet,
+    AssignedInfo,
+    StreamProperty,
+    Stage,
+    Gametitle,
+    Tips,
+    Amiibo,
+    MiiBody,
+    MiiHat,
+}
+
+impl Table {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Table::Chara => "ui_chara_db",
+            Table::CharaLayout => "ui_chara_layout_db",
+            Table::Series => "ui_series_db",
+            Table::Bgm => "ui_bgm_db",
+            Table::StreamSet => "stream_set",
+            Table::AssignedInfo => "assigned_info",
+            Table::StreamProperty => "stream_property",
+            Table::Stage => "ui_stage_db",
+            Table::Gametitle => "ui_gametitle_db",
+            Table::Tips => "ui_tips_db",
+            Table::Amiibo => "ui_amiibo_db",
+            Table::MiiBody => "ui_mii_body_db",
+            Table::MiiHat => "ui_mii_hat_db",
+        }
+    }
+
+    /// The field holding an entry's id.
+    pub fn id_field(&self) -> &'static str {
+        match self {
+            Table::Chara => "ui_chara_id",
+            Table::CharaLayout => "ui_layout_id",
+            Table::Series => "ui_series_id",
+            Table::Bgm => "ui_bgm_id",
+            Table::StreamSet => "stream_set_id",
+            Table::AssignedInfo => "info_id",
+            Table::StreamProperty => "stream_id",
+            Table::Stage => "ui_stage_id",
+            Table::Gametitle => "ui_gametitle_id",
+            Table::Tips => "ui_tips_id",
+            Table::Amiibo => "ui_amiibo_id",
+            Table::MiiBody => "ui_mii_body_id",
+            Table::MiiHat => "ui_mii_hat_id",
+        }
+    }
+
+    /// The table rows of `kind` go in. Playlists aren't tracked.
+    pub fn from_kind(kind: EntryKind) -> Option<Self> {
+        Some(match kind {
+            EntryKind::Chara => Table::Chara,
+            EntryKind::CharaLayout => Table::CharaLayout,
+            EntryKind::Series => Table::Series,
+            EntryKind::Bgm => Table::Bgm,
+            EntryKind::StreamSet => Table::StreamSet,
+            EntryKind::AssignedInfo => Table::AssignedInfo,
+            EntryKind::StreamProperty => Table::StreamProperty,
+            EntryKind::BgmPlaylist => return None,
+            EntryKind::Stage => Table::Stage,
+            EntryKind::Gametitle => Table::Gametitle,
+            EntryKind::Tips => Table::Tips,
+            EntryKind::Amiibo => Table::Amiibo,
+            EntryKind::MiiBody => Table::MiiBody,
+            EntryKind::MiiHat => Table::MiiHat,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Slot {
+    SaveNo,
+    // test_disp_order for bgm
+    DispOrder,
+    MenuValue,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 3] = [Slot::SaveNo, Slot::DispOrder, Slot::MenuValue];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Slot::SaveNo => "save_no",
+            Slot::DispOrder => "disp_order",
+            Slot::MenuValue => "menu_value",
+        }
+    }
+
+    /// The field holding the slot in `table`'s params.
+    pub fn field(&self, table: Table) -> &'static str {
+        match (table, self) {
+            (Table::Bgm, Slot::DispOrder) => "test_disp_order",
+            _ => self.name(),
+        }
+    }
+
+    /// The values the field can hold in `table`, or `None` if the table doesn't have it.
+    pub fn range(&self, table: Table) -> Option<RangeInclusive<i64>> {
+        let range = match (table, self) {
+            (Table::Chara | Table::Series, Slot::SaveNo | Slot::DispOrder) | (Table::Stage, Slot::DispOrder) => {
+                0..=i8::MAX as i64
+            }
+            (Table::Bgm | Table::Stage | Table::MiiBody | Table::MiiHat, Slot::SaveNo) | (Table::Bgm, Slot::DispOrder) => {
+                0..=i16::MAX as i64
+            }
+            (Table::Bgm, Slot::MenuValue) | (Table::MiiBody | Table::MiiHat, Slot::DispOrder) => 0..=i32::MAX as i64,
+            (Table::Tips, Slot::SaveNo | Slot::DispOrder) => 0..=u32::MAX as i64,
+            _ => return None,
+        };
+        Some(range)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// The same id was added twice, only the last one is kept.
+    DuplicateId { table: Table, id: Hash40 },
+    /// `id` uses a slot value `owner` already has.
+    SlotTaken { table: Table, slot: Slot, value: i64, owner: Hash40, id: Hash40 },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::DuplicateId { table, id } => write!(f, "{} was added to {} more than once!", id, table.name()),
+            Conflict::SlotTaken { table, slot, value, owner, id } => write!(
+                f,
+                "{} in {} uses {} {}, which {} already has!",
+                id,
+                table.name(),
+                slot.name(),
+                value,
+                owner
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// An entry the registry can record: its table, its id and the slots it takes.
+pub trait Tracked {
+    const TABLE: Table;
+
+    fn id(&self) -> u64;
+
+    fn slots(&self) -> Vec<(Slot, i64)> {
+        Vec::new()
+    }
+}
+
+macro_rules! tracked {
+    ($($entry:ty: $table:ident, $id:ident $(, [$($slot:ident = $field:ident),*])?;)*) => {
+        $(
+            impl Tracked for $entry {
+                const TABLE: Table = Table::$table;
+
+                fn id(&self) -> u64 {
+                    self.$id
+                }
+
+                $(
+                    fn slots(&self) -> Vec<(Slot, i64)> {
+                        // Negative values are how the game says "none", they never collide
+                        [$((Slot::$slot, self.$field.value().map(|value| *value as i64)),)*]
+                            .into_iter()
+                            .filter_map(|(slot, value)| Some((slot, value.filter(|value| *value >= 0)?)))
+                            .collect()
+                    }
+                )?
+            }
+        )*
+    };
+}
+
+// `entry: table, id field, [slot = field, ...];`
+tracked! {
+    CharacterDatabaseEntry: Chara, ui_chara_id, [SaveNo = save_no, DispOrder = disp_order];
+    CharacterLayoutDatabaseEntry: CharaLayout, ui_layout_id;
+    SeriesDatabaseEntry: Series, ui_series_id, [SaveNo = save_no, DispOrder = disp_order];
+    BgmDatabaseRootEntry: Bgm, ui_bgm_id, [SaveNo = save_no, DispOrder = test_disp_order, MenuValue = menu_value];
+    StreamSetEntry: StreamSet, stream_set_id;
+    AssignedInfoEntry: AssignedInfo, info_id;
+    StreamPropertyEntry: StreamProperty, stream_id;
+    StageDatabaseEntry: Stage, ui_stage_id, [SaveNo = save_no, DispOrder = disp_order];
+    GametitleDatabaseEntry: Gametitle, ui_gametitle_id;
+    TipsDatabaseEntry: Tips, ui_tips_id, [SaveNo = save_no, DispOrder = disp_order];
+    AmiiboDatabaseEntry: Amiibo, ui_amiibo_id;
+    MiiBodyDatabaseEntry: MiiBody, ui_mii_body_id, [SaveNo = save_no, DispOrder = disp_order];
+    MiiHatDatabaseEntry: MiiHat, ui_mii_hat_id, [SaveNo = save_no, DispOrder = disp_order];
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    ids: HashSet<(Table, u64)>,
+    // The id holding each value, None while only reserved
+    slots: BTreeMap<(Table, Slot), BTreeMap<i64, Option<u64>>>,
+    // The game's own ids, for the tables whose params were recorded
+    vanilla: BTreeMap<Table, HashSet<u64>>,
+    // And their rows, in the order they were recorded
+    params: BTreeMap<Table, Vec<ParamRow>>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `entry`, returning the conflicts it caused. They are also kept in [`Registry::conflicts`].
+    pub fn record<T: Tracked>(&mut self, entry: &T) -> Vec<Conflict> {
+        let table = T::TABLE;
+        let id = entry.id();
+        let mut conflicts = Vec::new();
+        if !self.ids.insert((table, id)) {
+            conflicts.push(Conflict::DuplicateId { table, id: Hash40(id) });
+        }
+        for (slot, value) in entry.slots() {
+            let owner = self.slots.entry((table, slot)).or_default().entry(value).or_insert(None);
+            match *owner {
+                Some(owner) if owner != id => conflicts.push(Conflict::SlotTaken {
+                    table,
+                    slot,
+                    value,
+                    owner: Hash40(owner),
+                    id: Hash40(id),
+                }),
+                Some(_) => {}
+                None => *owner = Some(id),
+            }
+        }
+        self.conflicts.extend(conflicts.iter().cloned());
+        conflicts
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    pub fn contains(&self, table: Table, id: u64) -> bool {
+        self.ids.contains(&(table, id))
+    }
+
+    /// Whether the game's params recorded with [`Registry::record_params`] have `id` in `table`.
+    pub fn is_vanilla(&self, table: Table, id: u64) -> bool {
+        self.vanilla.get(&table).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Whether the game's params for `table` were recorded, i.e. if [`Registry::is_vanilla`] knows its ids.
+    pub fn knows_vanilla(&self, table: Table) -> bool {
+        self.vanilla.contains_key(&table)
+    }
+
+    /// The game's `id` entry in `T`'s table as [`Registry::record_params`] recorded it, every value as `Overwrite`.
+    /// `None` if it wasn't recorded or its row doesn't convert to `T`.
+    pub fn vanilla_entry<T: Tracked + ParamEntry>(&self, id: u64) -> Option<T> {
+        let id = ParamValue::Hash40(Hash40(id));
+        let row = self.params.get(&T::TABLE)?.iter().find(|row| row.get(T::TABLE.id_field()) == Some(&id))?;
+        T::from_row_overwriting(row).ok()
+    }
+
+    /// Every recorded game entry in `T`'s table, like [`Registry::vanilla_entry`].
+    pub fn vanilla_entries<T: Tracked + ParamEntry>(&self) -> Vec<T> {
+        let rows = self.params.get(&T::TABLE).into_iter().flatten();
+        rows.filter_map(|row| T::from_row_overwriting(row).ok()).collect()
+    }
+
+    /// Whether `value` is used or reserved, and by which id if used.
+    pub fn owner(&self, table: Table, slot: Slot, value: i64) -> Option<Option<u64>> {
+        self.slots.get(&(table, slot))?.get(&value).copied()
+    }
+
+    /// Keeps `value` from being handed out, e.g. for slots the game or another mod uses. The first
+    /// entry recorded with it takes it over.
+    pub fn reserve(&mut self, table: Table, slot: Slot, value: i64) {
+        self.slots.entry((table, slot)).or_default().entry(value).or_insert(None);
+    }
+
+    /// Reserves and returns the lowest value for `slot` nothing uses, or `None` if the table doesn't have the slot
+    /// or every value is taken. Slots the game uses are only skipped once [`Registry::record_params`] knows them.
+    pub fn next_free(&mut self, table: Table, slot: Slot) -> Option<i64> {
+        let range = slot.range(table)?;
+        let taken = self.slots.entry((table, slot)).or_default();
+        let mut value = *range.start();
+        for used in taken.range(value..).map(|(used, _)| *used) {
+            if used != value {
+                break;
+            }
+            value += 1;
+        }
+        if !range.contains(&value) {
+            return None;
+        }
+        taken.insert(value, None);
+        Some(value)
+    }
+
+    pub fn next_free_save_no(&mut self, table: Table) -> Option<i64> {
+        self.next_free(table, Slot::SaveNo)
+    }
+
+    pub fn next_free_disp_order(&mut self, table: Table) -> Option<i64> {
+        self.next_free(table, Slot::DispOrder)
+    }
+
+    /// Records the ids and slots the game's own entries use, from its `ui_*_db.prc` params exported to XML and
+    /// read with [`crate::convert::parse_rows`]. Only the slots are taken, so mods may still edit the vanilla entries.
+    /// The rows are kept for [`Registry::vanilla_entry`], a row recorded again replaces the old one.
+    pub fn record_params(&mut self, rows: &[ParamRow]) {
+        for row in rows {
+            let Some(table) = EntryKind::detect(row).and_then(Table::from_kind) else {
+                continue;
+            };
+            let Some(ParamValue::Hash40(id)) = row.get(table.id_field()) else {
+                continue;
+            };
+            let rows = self.params.entry(table).or_default();
+            if !self.vanilla.entry(table).or_default().insert(id.0) {
+                rows.retain(|known| known.get(table.id_field()) != Some(&ParamValue::Hash40(*id)));
+            }
+            rows.push(row.clone());
+            for slot in Slot::ALL.into_iter().filter(|slot| slot.range(table).is_some()) {
+                // Negative values are how the game says "none" here too
+                if let Some(value) = row.get(slot.field(table)).and_then(ParamValue::as_i64).filter(|value| *value >= 0) {
+                    self.slots.entry((table, slot)).or_default().entry(value).or_insert(Some(id.0));
+                }
+            }
+        }
+    }
+
+    /// Records every entry of `manifest`.
+    #[cfg(feature = "serde")]
+    pub fn record_manifest(&mut self, manifest: &crate::manifest::Manifest) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        conflicts.extend(manifest.series_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.gametitle_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.chara_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.chara_layout_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.stream_property.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.assigned_info.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.stream_set.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.bgm_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.stage_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.tips_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.amiibo_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.mii_body_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts.extend(manifest.mii_hat_db.iter().flat_map(|entry| self.record(entry)));
+        conflicts
+    }
+}
+
+fn global() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Runs `f` on the registry the `add_*` functions record into.
+pub fn with_registry<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    f(&mut global().lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+}
+
+pub(crate) fn record<T: Tracked>(entry: &T) {
+    with_registry(|registry| registry.record(entry));
+}
+
+/// Records the ids and slots the game's own entries use, see [`Registry::record_params`].
+pub fn record_params(rows: &[ParamRow]) {
+    with_registry(|registry| registry.record_params(rows));
+}
+
+/// Every conflict between the entries added so far.
+pub fn conflicts() -> Vec<Conflict> {
+    with_registry(|registry| registry.conflicts().to_vec())
+}
+
+/// A `save_no` nothing added so far uses, reserved so the next call gets another one.
+pub fn next_free_save_no(table: Table) -> Option<i64> {
+    with_registry(|registry| registry.next_free_save_no(table))
+}
+
+/// A `disp_order` nothing added so far uses, reserved so the next call gets another one.
+pub fn next_free_disp_order(table: Table) -> Option<i64> {
+    with_registry(|registry| registry.next_free_disp_order(table))
+}
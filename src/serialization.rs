@@ -0,0 +1,249 @@
+//! Serde support for the entry field types. Every entry field goes through [`SerdeField`] so that
+//! hashes accept labels or hex and the `Overwrite`/`Optional` wrappers read naturally:
+//!
+//! ```toml
+//! name_id = "mario"              # Overwrite("mario")
+//! disp_order = { optional = 3 }  # Optional(Some(3))
+//!                                # left out: Optional(None)
+//! ```
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BoolType, CStrCSK, FloatType, Hash40, Hash40Map, Hash40Type, IntType, ShortType, SignedByteType, StringType,
+    UnsignedByteMap, UnsignedByteType, UnsignedIntType, UnsignedShortType,
+};
+
+impl Serialize for Hash40 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+thread_local! {
+    // Labels read while `collect_labels` runs, None outside of it
+    static READ_LABELS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Runs `read`, also returning every label it read a hash from. Reading never adds labels on its own, it's up
+/// to the caller to pass these to [`add_label`](crate::hash40::add_label).
+pub(crate) fn collect_labels<T>(read: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let outer = READ_LABELS.with(|labels| labels.replace(Some(Vec::new())));
+    let value = read();
+    let labels = READ_LABELS.with(|labels| labels.replace(outer)).unwrap_or_default();
+    (value, labels)
+}
+
+struct Hash40Visitor;
+
+impl<'de> Visitor<'de> for Hash40Visitor {
+    type Value = Hash40;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a label, a hex string or an integer hash")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        if value.starts_with("0x") || value.starts_with("0X") {
+            value.parse().map_err(de::Error::custom)
+        } else {
+            READ_LABELS.with(|labels| labels.borrow_mut().as_mut().map(|labels| labels.push(value.to_string())));
+            Ok(crate::hash40::hash40(value))
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(Hash40(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u64::try_from(value).map(Hash40).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash40 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(Hash40Visitor)
+    }
+}
+
+impl Serialize for CStrCSK {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str().unwrap_or_default())
+    }
+}
+
+impl<'de> Deserialize<'de> for CStrCSK {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(CStrCSK::new(&c_string(deserializer)?))
+    }
+}
+
+// Strings handed to the plugin as C strings, which can't hold a NUL byte
+fn check_nul<E: de::Error>(s: String) -> Result<String, E> {
+    if s.contains('\0') {
+        return Err(E::custom(format!("{:?} contains a NUL byte", s)));
+    }
+    Ok(s)
+}
+
+pub(crate) fn c_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    check_nul(String::deserialize(deserializer)?)
+}
+
+pub(crate) fn c_strings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?.into_iter().map(check_nul).collect()
+}
+
+/// How a single entry field is (de)serialized.
+pub trait SerdeField: Sized {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+    // Fields that are unset get skipped when serializing
+    fn is_unset(&self) -> bool;
+}
+
+pub fn serialize<T: SerdeField, S: Serializer>(field: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    field.serialize_field(serializer)
+}
+
+pub fn deserialize<'de, T: SerdeField, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    T::deserialize_field(deserializer)
+}
+
+pub fn is_unset<T: SerdeField>(field: &T) -> bool {
+    field.is_unset()
+}
+
+impl SerdeField for u64 {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Hash40(*self).serialize(serializer)
+    }
+
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Hash40::deserialize(deserializer).map(|hash| hash.0)
+    }
+
+    fn is_unset(&self) -> bool {
+        false
+    }
+}
+
+impl SerdeField for Option<u64> {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.map(Hash40).serialize(serializer)
+    }
+
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<Hash40>::deserialize(deserializer).map(|hash| hash.map(|hash| hash.0))
+    }
+
+    fn is_unset(&self) -> bool {
+        self.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum Repr<T> {
+    Optional {
+        optional: Option<T>,
+    },
+    Overwrite(T),
+}
+
+// Wrapper types for the parts of the enums that don't serialize the way they should on their own
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct HashRepr(Hash40);
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct StringRepr(String);
+
+struct MapRepr<T>(HashMap<u64, T>);
+
+impl<T: SerdeField> Serialize for MapRepr<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Value<'a, T>(&'a T);
+
+        impl<T: SerdeField> Serialize for Value<'_, T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize_field(serializer)
+            }
+        }
+
+        let mut keys: Vec<&u64> = self.0.keys().collect();
+        keys.sort();
+        let mut map = serializer.serialize_map(Some(keys.len()))?;
+        for key in keys {
+            map.serialize_entry(&Hash40(*key), &Value(&self.0[key]))?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, T: SerdeField> Deserialize<'de> for MapRepr<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Value<T>(T);
+
+        impl<'de, T: SerdeField> Deserialize<'de> for Value<T> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                T::deserialize_field(deserializer).map(Value)
+            }
+        }
+
+        let map = HashMap::<Hash40, Value<T>>::deserialize(deserializer)?;
+        Ok(MapRepr(map.into_iter().map(|(key, value)| (key.0, value.0)).collect()))
+    }
+}
+
+macro_rules! impl_serde_field {
+    ($enum_type:ident, $repr:ty, |$to:ident| $to_repr:expr, |$from:ident| $from_repr:expr) => {
+        impl SerdeField for $enum_type {
+            fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let repr: Repr<$repr> = match self {
+                    $enum_type::Overwrite($to) => Repr::Overwrite($to_repr),
+                    $enum_type::Optional(Some($to)) => Repr::Optional { optional: Some($to_repr) },
+                    $enum_type::Optional(None) => Repr::Optional { optional: None },
+                };
+                repr.serialize(serializer)
+            }
+
+            fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Ok(match Repr::<$repr>::deserialize(deserializer)? {
+                    Repr::Overwrite($from) => $enum_type::Overwrite($from_repr),
+                    Repr::Optional { optional: Some($from) } => $enum_type::Optional(Some($from_repr)),
+                    Repr::Optional { optional: None } => $enum_type::Optional(None),
+                })
+            }
+
+            fn is_unset(&self) -> bool {
+                matches!(self, $enum_type::Optional(None))
+            }
+        }
+    };
+    ($enum_type:ident) => {
+        impl_serde_field!($enum_type, _, |value| *value, |value| value);
+    };
+}
+
+impl_serde_field!(StringType, StringRepr, |value| StringRepr(value.as_str().unwrap_or_default().to_string()), |value| {
+    CStrCSK::new(&check_nul(value.0)?)
+});
+impl_serde_field!(Hash40Type, HashRepr, |value| HashRepr(Hash40(*value)), |value| value.0 .0);
+impl_serde_field!(ShortType);
+impl_serde_field!(UnsignedShortType);
+impl_serde_field!(IntType);
+impl_serde_field!(UnsignedIntType);
+impl_serde_field!(FloatType);
+impl_serde_field!(BoolType);
+impl_serde_field!(SignedByteType);
+impl_serde_field!(UnsignedByteType);
+impl_serde_field!(Hash40Map, MapRepr<Hash40Type>, |value| MapRepr(value.clone()), |value| value.0);
+impl_serde_field!(UnsignedByteMap, MapRepr<UnsignedByteType>, |value| MapRepr(value.clone()), |value| value.0);
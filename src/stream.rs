@@ -0,0 +1,399 @@
+//! Typed access to the `stream_set`, `assigned_info` and `stream_property` fields.
+//!
+//! A stream set lists up to 16 `assigned_info` ids, `info0` to `info15`, and every info plays its
+//! stream when its `condition` holds, combined with the others as its `condition_process` says.
+//! The stream property then says where in the file playback starts and ends, as [`TimePoint`]s:
+//!
+//! ```ignore
+//! let mut set = StreamSetEntry::clone_from("set_a01_smb_chijyou");
+//! set.inherit_infos(&vanilla_set);
+//! set.push_info(hash40("info_custom_night"))?;
+//! let night = AssignedInfoEntry::new("info_custom_night")
+//!     .stream_id("stream_custom_night")
+//!     .condition(SoundCondition::NightStage)
+//!     .condition_process(ConditionProcess::Add);
+//! let stream = StreamPropertyEntry::new("stream_custom_night")
+//!     .data_name0("custom_night")
+//!     .end_point(TimePoint::from_millis(134_640))
+//!     .start_point_suddendeath("01:23.500".parse::<TimePoint>()?);
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{AssignedInfoEntry, CStrCSK, Hash40, Hash40Type, StreamPropertyEntry, StreamSetEntry, StringType};
+
+pub const STREAM_SET_INFOS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamSetError {
+    /// All 16 infos are used.
+    Full,
+    IndexOutOfRange(usize),
+    /// The set is a clone that leaves some infos to its `clone_from`, so where they are isn't known.
+    UnresolvedClone,
+}
+
+impl fmt::Display for StreamSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamSetError::Full => write!(f, "All {} infos of the stream set are used!", STREAM_SET_INFOS),
+            StreamSetError::IndexOutOfRange(index) => {
+                write!(f, "Info {} is out of range! Stream sets have infos 0 to {}", index, STREAM_SET_INFOS - 1)
+            }
+            StreamSetError::UnresolvedClone => {
+                write!(f, "The stream set inherits infos from its clone_from! Fill them in with inherit_infos first.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamSetError {}
+
+// `info field;` for every info, in order
+macro_rules! stream_set_infos {
+    ($($info:ident;)*) => {
+        impl StreamSetEntry {
+            /// The infos that are set, in order. The empty hash reads as `None`, like an unset info.
+            pub fn infos(&self) -> [Option<Hash40>; STREAM_SET_INFOS] {
+                [$(self.$info.value().filter(|info| **info != 0).map(|info| Hash40(*info))),*]
+            }
+
+            fn info_fields(&self) -> [&Hash40Type; STREAM_SET_INFOS] {
+                [$(&self.$info),*]
+            }
+
+            fn info_fields_mut(&mut self) -> [&mut Hash40Type; STREAM_SET_INFOS] {
+                [$(&mut self.$info),*]
+            }
+        }
+    };
+}
+
+stream_set_infos! {
+    info0;
+    info1;
+    info2;
+    info3;
+    info4;
+    info5;
+    info6;
+    info7;
+    info8;
+    info9;
+    info10;
+    info11;
+    info12;
+    info13;
+    info14;
+    info15;
+}
+
+impl StreamSetEntry {
+    /// Writes the infos that differ from [`StreamSetEntry::infos`], `None` as the empty hash. The others are left
+    /// as they are, so a clone still inherits the ones it doesn't set.
+    pub fn set_infos(&mut self, infos: [Option<Hash40>; STREAM_SET_INFOS]) {
+        let current = self.infos();
+        for ((field, info), current) in self.info_fields_mut().into_iter().zip(infos).zip(current) {
+            if info != current {
+                *field = Hash40Type::Overwrite(info.map_or(0, |info| info.0));
+            }
+        }
+    }
+
+    /// Fills the infos this entry leaves unset from `source`, the resolved entry it's cloned from, e.g. read from
+    /// the game's params. Infos `source` doesn't set either are written empty.
+    pub fn inherit_infos(&mut self, source: &StreamSetEntry) {
+        for (field, source) in self.info_fields_mut().into_iter().zip(source.info_fields()) {
+            if !field.is_set() {
+                *field = Hash40Type::Overwrite(source.value().copied().unwrap_or(0));
+            }
+        }
+    }
+
+    // Where the infos of a clone are is only known once every one of them is set
+    fn check_resolved(&self) -> Result<(), StreamSetError> {
+        if self.clone_from_stream_set_id.is_some() && !self.info_fields().iter().all(|field| field.is_set()) {
+            return Err(StreamSetError::UnresolvedClone);
+        }
+        Ok(())
+    }
+
+    /// Adds `info` after the last one set, returning its index. A clone needs [`StreamSetEntry::inherit_infos`] first.
+    pub fn push_info(&mut self, info: Hash40) -> Result<usize, StreamSetError> {
+        self.check_resolved()?;
+        let mut infos = self.infos();
+        let index = infos.iter().rposition(Option::is_some).map_or(0, |last| last + 1);
+        if index == STREAM_SET_INFOS {
+            return Err(StreamSetError::Full);
+        }
+        infos[index] = Some(info);
+        self.set_infos(infos);
+        Ok(index)
+    }
+
+    /// Puts `info` at `index`, moving the ones from there on up by one. A clone needs
+    /// [`StreamSetEntry::inherit_infos`] first.
+    pub fn insert_info(&mut self, index: usize, info: Hash40) -> Result<(), StreamSetError> {
+        if index >= STREAM_SET_INFOS {
+            return Err(StreamSetError::IndexOutOfRange(index));
+        }
+        self.check_resolved()?;
+        let mut infos = self.infos();
+        if infos[STREAM_SET_INFOS - 1].is_some() {
+            return Err(StreamSetError::Full);
+        }
+        infos[index..].rotate_right(1);
+        infos[index] = Some(info);
+        self.set_infos(infos);
+        Ok(())
+    }
+}
+
+// Builds an enum of the known labels of a hash field, with `Unknown` for every other hash
+macro_rules! hash_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident => $label:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            Unknown(Hash40),
+        }
+
+        impl $name {
+            pub const KNOWN: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn hash(&self) -> Hash40 {
+                match self {
+                    $($name::$variant => crate::hash40($label),)*
+                    $name::Unknown(hash) => *hash,
+                }
+            }
+
+            /// The param label, if it's a known value.
+            pub fn label(&self) -> Option<&'static str> {
+                match self {
+                    $($name::$variant => Some($label),)*
+                    $name::Unknown(_) => None,
+                }
+            }
+        }
+
+        impl From<Hash40> for $name {
+            fn from(hash: Hash40) -> Self {
+                $name::KNOWN.iter().copied().find(|known| known.hash() == hash).unwrap_or($name::Unknown(hash))
+            }
+        }
+
+        impl From<$name> for Hash40 {
+            fn from(value: $name) -> Self {
+                value.hash()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.label() {
+                    Some(label) => f.write_str(label),
+                    None => write!(f, "{}", self.hash()),
+                }
+            }
+        }
+
+        impl crate::builder::FieldValue<Hash40Type> for $name {
+            fn into_field(self) -> Hash40Type {
+                if let Some(label) = self.label() {
+                    crate::hash40::add_label(label);
+                }
+                Hash40Type::Overwrite(self.hash().0)
+            }
+        }
+    };
+}
+
+hash_enum! {
+    /// `AssignedInfoEntry::condition`, when the info's stream plays. Only the values this crate has seen in the
+    /// game's params have a variant. Any other is kept as `Unknown` and prints by name once its label is loaded,
+    /// e.g. from ParamLabels.csv with [`crate::hash40::load_labels_from_str`].
+    SoundCondition {
+        /// Always.
+        None => "sound_condition_none",
+        /// On the night form of a stage.
+        NightStage => "sound_condition_night_stage",
+    }
+}
+
+hash_enum! {
+    /// `AssignedInfoEntry::condition_process`, how the info combines with the others in its stream set. Other
+    /// values are kept as `Unknown`, like for [`SoundCondition`].
+    ConditionProcess {
+        /// Adds the info to the ones that can be picked.
+        Add => "sound_condition_process_add",
+    }
+}
+
+impl AssignedInfoEntry {
+    pub fn get_condition(&self) -> Option<SoundCondition> {
+        self.condition.value().map(|condition| SoundCondition::from(Hash40(*condition)))
+    }
+
+    pub fn set_condition(&mut self, condition: SoundCondition) {
+        self.condition = crate::builder::FieldValue::into_field(condition);
+    }
+
+    pub fn get_condition_process(&self) -> Option<ConditionProcess> {
+        self.condition_process.value().map(|process| ConditionProcess::from(Hash40(*process)))
+    }
+
+    pub fn set_condition_process(&mut self, process: ConditionProcess) {
+        self.condition_process = crate::builder::FieldValue::into_field(process);
+    }
+}
+
+/// How many frames the game runs a second, for `fadeout_frame`.
+pub const FRAMES_PER_SECOND: u64 = 60;
+
+/// A position in a stream, like the `hh:mm:ss.mmm` strings `stream_property` uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimePoint {
+    millis: u64,
+}
+
+impl TimePoint {
+    pub const ZERO: TimePoint = TimePoint { millis: 0 };
+
+    pub const fn from_millis(millis: u64) -> Self {
+        TimePoint { millis }
+    }
+
+    /// The sample `samples` in at `sample_rate`, rounded to the nearest millisecond. Panics if `sample_rate` is 0.
+    pub const fn from_samples(samples: u64, sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as u64;
+        TimePoint { millis: (samples * 1000 + sample_rate / 2) / sample_rate }
+    }
+
+    pub const fn from_frames(frames: u64) -> Self {
+        TimePoint { millis: frames * 1000 / FRAMES_PER_SECOND }
+    }
+
+    pub const fn as_millis(&self) -> u64 {
+        self.millis
+    }
+
+    /// The sample at this point at `sample_rate`, rounded down.
+    pub const fn to_samples(&self, sample_rate: u32) -> u64 {
+        self.millis * sample_rate as u64 / 1000
+    }
+
+    /// The frame at this point, rounded to the nearest one.
+    pub const fn to_frames(&self) -> u64 {
+        (self.millis * FRAMES_PER_SECOND + 500) / 1000
+    }
+
+    /// The point set in a field, `Ok(None)` if it isn't.
+    pub fn from_field(field: &StringType) -> Result<Option<Self>, ParseTimePointError> {
+        match field.value() {
+            Some(value) => value.as_str().unwrap_or_default().parse().map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl fmt::Display for TimePoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.millis / 1000;
+        write!(f, "{:02}:{:02}:{:02}.{:03}", seconds / 3600, seconds / 60 % 60, seconds % 60, self.millis % 1000)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimePointError(pub String);
+
+impl fmt::Display for ParseTimePointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a time point! Expected hh:mm:ss.mmm or mm:ss.mmm", self.0)
+    }
+}
+
+impl std::error::Error for ParseTimePointError {}
+
+// Takes "00:01:23.500", "01:23.500" or "01:23" (0 ms), with 1 to 3 digits of milliseconds
+impl FromStr for TimePoint {
+    type Err = ParseTimePointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseTimePointError(s.to_string());
+        let number = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(err());
+            }
+            part.parse::<u64>().map_err(|_| err())
+        };
+        let (rest, millis) = match s.trim().split_once('.') {
+            Some((rest, fraction)) if fraction.len() <= 3 => (rest, number(fraction)? * 10u64.pow(3 - fraction.len() as u32)),
+            Some(_) => return Err(err()),
+            None => (s.trim(), 0),
+        };
+        let parts = rest.split(':').map(number).collect::<Result<Vec<_>, _>>()?;
+        let (hours, minutes, seconds) = match parts[..] {
+            [minutes, seconds] => (0, minutes, seconds),
+            [hours, minutes, seconds] if minutes < 60 => (hours, minutes, seconds),
+            _ => return Err(err()),
+        };
+        if seconds >= 60 {
+            return Err(err());
+        }
+        Ok(TimePoint::from_millis(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis))
+    }
+}
+
+impl crate::builder::FieldValue<StringType> for TimePoint {
+    fn into_field(self) -> StringType {
+        StringType::Overwrite(CStrCSK::new(&self.to_string()))
+    }
+}
+
+impl StreamPropertyEntry {
+    pub fn data_names(&self) -> [&StringType; 5] {
+        [&self.data_name0, &self.data_name1, &self.data_name2, &self.data_name3, &self.data_name4]
+    }
+
+    pub fn data_names_mut(&mut self) -> [&mut StringType; 5] {
+        [&mut self.data_name0, &mut self.data_name1, &mut self.data_name2, &mut self.data_name3, &mut self.data_name4]
+    }
+
+    pub fn start_points(&self) -> [&StringType; 5] {
+        [&self.start_point0, &self.start_point1, &self.start_point2, &self.start_point3, &self.start_point4]
+    }
+
+    pub fn start_points_mut(&mut self) -> [&mut StringType; 5] {
+        [
+            &mut self.start_point0,
+            &mut self.start_point1,
+            &mut self.start_point2,
+            &mut self.start_point3,
+            &mut self.start_point4,
+        ]
+    }
+
+    /// Every start point field with its name: the five numbered ones, then sudden death and transition.
+    pub fn named_start_points(&self) -> [(&'static str, &StringType); 7] {
+        [
+            ("start_point0", &self.start_point0),
+            ("start_point1", &self.start_point1),
+            ("start_point2", &self.start_point2),
+            ("start_point3", &self.start_point3),
+            ("start_point4", &self.start_point4),
+            ("start_point_suddendeath", &self.start_point_suddendeath),
+            ("start_point_transition", &self.start_point_transition),
+        ]
+    }
+
+    pub fn get_fadeout(&self) -> Option<TimePoint> {
+        self.fadeout_frame.value().map(|frames| TimePoint::from_frames(*frames as u64))
+    }
+
+    /// Sets `fadeout_frame` to the frames closest to `fadeout`, at most `u16::MAX` (about 18 minutes).
+    pub fn set_fadeout(&mut self, fadeout: TimePoint) {
+        self.fadeout_frame = crate::UnsignedShortType::Overwrite(fadeout.to_frames().min(u16::MAX as u64) as u16);
+    }
+}
@@ -0,0 +1,376 @@
+//! Checks entries for mistakes the plugin would otherwise take silently.
+//!
+//! Errors are entries that won't work as written, warnings are ones that probably won't do what
+//! was meant. Every entry type implements [`Validate`], and so does a whole [`crate::manifest::Manifest`],
+//! which also checks the entries against each other.
+//!
+//! Slots the base game already uses aren't known here. They're conflicts in a [`crate::registry::Registry`]
+//! that has recorded the game's params with [`crate::registry::Registry::record_params`].
+use std::fmt;
+
+use crate::chara::{IndexKey, LabelKey};
+use crate::stream::TimePoint;
+use crate::{
+    AmiiboDatabaseEntry, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, GametitleDatabaseEntry, Hash40, MiiBodyDatabaseEntry, MiiHatDatabaseEntry,
+    SeriesDatabaseEntry, StageDatabaseEntry, StreamPropertyEntry, StreamSetEntry, StringType, TipsDatabaseEntry,
+};
+
+// Colors the game ships layouts for. Fighters with more need a layout for each extra color.
+pub const VANILLA_COLOR_NUM: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The entry's own id is 0.
+    MissingId,
+    /// Not cloned from anything, and a field every new entry needs isn't set.
+    MissingField,
+    /// `color_num` goes past the vanilla colors. With `missing` empty, the layouts couldn't be checked.
+    ColorsWithoutLayouts { color_num: u8, missing: Vec<u8> },
+    /// A map key that isn't one of the labels the table has.
+    UnknownMapKey(Hash40),
+    /// A time field that doesn't parse as a [`TimePoint`].
+    InvalidTimePoint(String),
+    /// A start point at or after `end_point`.
+    PastEndPoint { point: TimePoint, end_point: TimePoint },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // The entry struct and its id
+    pub entry: &'static str,
+    pub id: Hash40,
+    pub field: &'static str,
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {} {} {}: ", severity, self.entry, self.id, self.field)?;
+        match &self.kind {
+            DiagnosticKind::MissingId => write!(f, "the id can't be 0"),
+            DiagnosticKind::MissingField => write!(f, "has to be set when not cloning from another entry"),
+            DiagnosticKind::ColorsWithoutLayouts { color_num, missing } if missing.is_empty() => {
+                write!(f, "{} colors need layouts for every color past {}", color_num, VANILLA_COLOR_NUM)
+            }
+            DiagnosticKind::ColorsWithoutLayouts { color_num, missing } => {
+                write!(f, "{} colors but there are no layouts for colors {:?}", color_num, missing)
+            }
+            DiagnosticKind::UnknownMapKey(key) => write!(f, "{} is not a known key", key),
+            DiagnosticKind::InvalidTimePoint(value) => write!(f, "{:?} is not a hh:mm:ss.mmm time", value),
+            DiagnosticKind::PastEndPoint { point, end_point } => {
+                write!(f, "{} isn't before the end point {}", point, end_point)
+            }
+        }
+    }
+}
+
+pub trait Validate {
+    fn validate(&self) -> Vec<Diagnostic>;
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+// Collects the diagnostics of one entry
+struct Checker {
+    entry: &'static str,
+    id: u64,
+    cloned: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn new(entry: &'static str, id_field: &'static str, id: u64, cloned: bool) -> Self {
+        let mut checker = Checker { entry, id, cloned, diagnostics: Vec::new() };
+        if id == 0 {
+            checker.push(Severity::Error, id_field, DiagnosticKind::MissingId);
+        }
+        checker
+    }
+
+    fn push(&mut self, severity: Severity, field: &'static str, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { severity, entry: self.entry, id: Hash40(self.id), field, kind });
+    }
+
+    fn require(&mut self, field: &'static str, set: bool) {
+        if !self.cloned && !set {
+            self.push(Severity::Error, field, DiagnosticKind::MissingField);
+        }
+    }
+
+    fn time_point(&mut self, field: &'static str, value: &StringType) -> Option<TimePoint> {
+        match TimePoint::from_field(value) {
+            Ok(point) => point,
+            Err(err) => {
+                self.push(Severity::Error, field, DiagnosticKind::InvalidTimePoint(err.0));
+                None
+            }
+        }
+    }
+
+    fn map_keys<'a>(&mut self, field: &'static str, keys: impl Iterator<Item = &'a u64>, known: &[u64]) {
+        let mut unknown: Vec<u64> = keys.filter(|key| !known.contains(key)).copied().collect();
+        unknown.sort();
+        for key in unknown {
+            self.push(Severity::Error, field, DiagnosticKind::UnknownMapKey(Hash40(key)));
+        }
+    }
+}
+
+/// The keys `CharacterDatabaseEntry::extra_index_maps` takes: `cXX_index`, `nXX_index` and `cXX_group`.
+pub fn chara_index_map_keys() -> Vec<u64> {
+    IndexKey::ALL.iter().flat_map(|key| (0..=u8::MAX).map(|slot| key.key(slot))).collect()
+}
+
+/// The keys `CharacterDatabaseEntry::extra_hash_maps` takes: `characall_label_cXX` and `characall_label_article_cXX`.
+pub fn chara_hash_map_keys() -> Vec<u64> {
+    LabelKey::ALL.iter().flat_map(|key| (0..=u8::MAX).map(|slot| key.key(slot))).collect()
+}
+
+impl Validate for CharacterDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("CharacterDatabaseEntry", "ui_chara_id", self.ui_chara_id, self.clone_from_ui_chara_id.is_some());
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("fighter_kind", self.fighter_kind.is_set());
+        checker.require("fighter_type", self.fighter_type.is_set());
+        checker.require("ui_series_id", self.ui_series_id.is_set());
+        checker.require("color_num", self.color_num.is_set());
+        if let Some(color_num) = self.color_num.value().filter(|color_num| **color_num > VANILLA_COLOR_NUM) {
+            checker.push(
+                Severity::Warning,
+                "color_num",
+                DiagnosticKind::ColorsWithoutLayouts { color_num: *color_num, missing: Vec::new() },
+            );
+        }
+        if let Some(map) = self.extra_index_maps.value() {
+            checker.map_keys("extra_index_maps", map.keys(), &chara_index_map_keys());
+        }
+        if let Some(map) = self.extra_hash_maps.value() {
+            checker.map_keys("extra_hash_maps", map.keys(), &chara_hash_map_keys());
+        }
+        checker.diagnostics
+    }
+}
+
+impl Validate for CharacterLayoutDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker = Checker::new(
+            "CharacterLayoutDatabaseEntry",
+            "ui_layout_id",
+            self.ui_layout_id,
+            self.clone_from_ui_layout_id.is_some(),
+        );
+        checker.require("ui_chara_id", self.ui_chara_id.is_set());
+        checker.require("chara_color", self.chara_color.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for SeriesDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("SeriesDatabaseEntry", "ui_series_id", self.ui_series_id, self.clone_from_ui_series_id.is_some());
+        checker.require("name_id", self.name_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for BgmDatabaseRootEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("BgmDatabaseRootEntry", "ui_bgm_id", self.ui_bgm_id, self.clone_from_ui_bgm_id.is_some());
+        checker.require("stream_set_id", self.stream_set_id.is_set());
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("record_type", self.record_type.is_set());
+        checker.require("ui_gametitle_id", self.ui_gametitle_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for StreamSetEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("StreamSetEntry", "stream_set_id", self.stream_set_id, self.clone_from_stream_set_id.is_some());
+        checker.require("info0", self.info0.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for AssignedInfoEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker = Checker::new("AssignedInfoEntry", "info_id", self.info_id, self.clone_from_info_id.is_some());
+        checker.require("stream_id", self.stream_id.is_set());
+        checker.require("condition", self.condition.is_set());
+        checker.require("condition_process", self.condition_process.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for StreamPropertyEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("StreamPropertyEntry", "stream_id", self.stream_id, self.clone_from_stream_id.is_some());
+        checker.require("data_name0", self.data_name0.is_set());
+        let end_point = checker.time_point("end_point", &self.end_point);
+        for (field, value) in self.named_start_points() {
+            let point = checker.time_point(field, value);
+            // A zero end point is left to the game
+            if let (Some(point), Some(end_point)) = (point, end_point.filter(|end_point| *end_point > TimePoint::ZERO)) {
+                if point >= end_point {
+                    checker.push(Severity::Error, field, DiagnosticKind::PastEndPoint { point, end_point });
+                }
+            }
+        }
+        checker.diagnostics
+    }
+}
+
+impl Validate for BgmPlaylistEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        Checker::new("BgmPlaylistEntry", "ui_bgm_id", self.ui_bgm_id, true).diagnostics
+    }
+}
+
+impl Validate for StageDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("StageDatabaseEntry", "ui_stage_id", self.ui_stage_id, self.clone_from_ui_stage_id.is_some());
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("stage_place_id", self.stage_place_id.is_set());
+        checker.require("ui_series_id", self.ui_series_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for GametitleDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker = Checker::new(
+            "GametitleDatabaseEntry",
+            "ui_gametitle_id",
+            self.ui_gametitle_id,
+            self.clone_from_ui_gametitle_id.is_some(),
+        );
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("ui_series_id", self.ui_series_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for TipsDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("TipsDatabaseEntry", "ui_tips_id", self.ui_tips_id, self.clone_from_ui_tips_id.is_some());
+        checker.require("level", self.level.is_set());
+        checker.require("topic", self.topic.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for AmiiboDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("AmiiboDatabaseEntry", "ui_amiibo_id", self.ui_amiibo_id, self.clone_from_ui_amiibo_id.is_some());
+        checker.require("ui_chara_id", self.ui_chara_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for MiiBodyDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker = Checker::new(
+            "MiiBodyDatabaseEntry",
+            "ui_mii_body_id",
+            self.ui_mii_body_id,
+            self.clone_from_ui_mii_body_id.is_some(),
+        );
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("mii_body_id", self.mii_body_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl Validate for MiiHatDatabaseEntry {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut checker =
+            Checker::new("MiiHatDatabaseEntry", "ui_mii_hat_id", self.ui_mii_hat_id, self.clone_from_ui_mii_hat_id.is_some());
+        checker.require("name_id", self.name_id.is_set());
+        checker.require("mii_hat_id", self.mii_hat_id.is_set());
+        checker.diagnostics
+    }
+}
+
+impl<T: Validate> Validate for [T] {
+    fn validate(&self) -> Vec<Diagnostic> {
+        self.iter().flat_map(Validate::validate).collect()
+    }
+}
+
+// Every entry, then the extra colors of the fighters against the layouts in the batch
+impl Validate for crate::batch::Batch {
+    fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        diagnostics.extend(self.series_db.validate());
+        diagnostics.extend(self.gametitle_db.validate());
+        diagnostics.extend(self.chara_db.validate());
+        diagnostics.extend(self.chara_layout_db.validate());
+        diagnostics.extend(self.stream_property.validate());
+        diagnostics.extend(self.assigned_info.validate());
+        diagnostics.extend(self.stream_set.validate());
+        diagnostics.extend(self.bgm_db.validate());
+        diagnostics.extend(self.playlists.iter().flat_map(|playlist| playlist.tracks.validate()));
+        diagnostics.extend(self.stage_db.validate());
+        diagnostics.extend(self.tips_db.validate());
+        diagnostics.extend(self.amiibo_db.validate());
+        diagnostics.extend(self.mii_body_db.validate());
+        diagnostics.extend(self.mii_hat_db.validate());
+
+        for chara in self.chara_db.iter() {
+            let Some(color_num) = chara.color_num.value().copied().filter(|color_num| *color_num > VANILLA_COLOR_NUM)
+            else {
+                continue;
+            };
+            let missing: Vec<u8> = (VANILLA_COLOR_NUM..color_num)
+                .filter(|color| {
+                    !self.chara_layout_db.iter().any(|layout| {
+                        layout.ui_chara_id.value() == Some(&chara.ui_chara_id) && layout.chara_color.value() == Some(color)
+                    })
+                })
+                .collect();
+            diagnostics.retain(|diagnostic| {
+                !(diagnostic.id.0 == chara.ui_chara_id
+                    && matches!(diagnostic.kind, DiagnosticKind::ColorsWithoutLayouts { .. }))
+            });
+            if !missing.is_empty() {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    entry: "CharacterDatabaseEntry",
+                    id: Hash40(chara.ui_chara_id),
+                    field: "color_num",
+                    kind: DiagnosticKind::ColorsWithoutLayouts { color_num, missing },
+                });
+            }
+        }
+        diagnostics
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Validate for crate::manifest::Manifest {
+    fn validate(&self) -> Vec<Diagnostic> {
+        crate::batch::Batch::from(self).validate()
+    }
+}
@@ -0,0 +1,131 @@
+use the_csk_collection_api::audio_header::{self, AudioFormat, AudioHeaderError, AudioInfo};
+use the_csk_collection_api::hash40;
+
+fn idsp(sample_rate: u32, total_samples: u32, loop_start: u32, loop_end: u32) -> Vec<u8> {
+    let mut data = b"IDSP".to_vec();
+    for value in [0, 2, sample_rate, total_samples, loop_start, loop_end, 8, 0x60, 0x60, 0xC0, 0] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data
+}
+
+// A bare Switch opus of `packets` of (TOC, frame count byte) at 48kHz, with an empty context chunk
+fn lopus(packets: &[(u8, u8)], pre_skip: u16) -> Vec<u8> {
+    let mut data = Vec::new();
+    let push = |data: &mut Vec<u8>, value: u32| data.extend_from_slice(&value.to_le_bytes());
+    push(&mut data, 0x80000001);
+    push(&mut data, 0x18);
+    data.extend_from_slice(&[0, 2, 0, 0]);
+    push(&mut data, 48000);
+    push(&mut data, 0x30);
+    push(&mut data, 0);
+    push(&mut data, 0x20);
+    data.extend_from_slice(&pre_skip.to_le_bytes());
+    data.resize(0x20, 0);
+
+    for value in [0x80000003, 0x10, 0, 0] {
+        push(&mut data, value);
+    }
+
+    let mut packet_data = Vec::new();
+    for (toc, count) in packets {
+        packet_data.extend_from_slice(&3u32.to_be_bytes());
+        packet_data.extend_from_slice(&0u32.to_be_bytes());
+        packet_data.extend_from_slice(&[*toc, *count, 0]);
+    }
+    push(&mut data, 0x80000004);
+    push(&mut data, packet_data.len() as u32);
+    data.extend_from_slice(&packet_data);
+    data
+}
+
+// What Smash's .lopus files are: the Switch opus behind Namco's OPUS header with the length and loop
+fn namco_lopus(total_samples: u32, loop_start: i32, loop_end: i32, packets: &[(u8, u8)], pre_skip: u16) -> Vec<u8> {
+    let mut data = b"OPUS".to_vec();
+    for value in [0, total_samples, 2] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&loop_start.to_le_bytes());
+    data.extend_from_slice(&loop_end.to_le_bytes());
+    data.resize(0x30, 0);
+    data.extend(lopus(packets, pre_skip));
+    data
+}
+
+#[test]
+fn idsp_headers_fill_bgm_properties() {
+    let info = AudioInfo::parse(&idsp(32000, 320000, 16000, 320000)).unwrap();
+    assert_eq!(info.format, AudioFormat::Idsp);
+    assert_eq!((info.channels, info.sample_rate, info.total_samples), (2, 32000, 320000));
+    assert_eq!(info.duration().to_string(), "00:00:10.000");
+
+    let entry = audio_header::bgm_property_from_audio("bgm_custom", &idsp(32000, 320000, 16000, 320000)).unwrap();
+    assert_eq!(entry.stream_name.0, hash40("bgm_custom").0);
+    assert_eq!((entry.loop_start_sample, entry.loop_start_ms), (16000, 500));
+    assert_eq!((entry.loop_end_sample, entry.loop_end_ms), (320000, 10000));
+    assert_eq!((entry.duration_sample, entry.duration_ms), (320000, 10000));
+
+    // Streams that don't loop play all the way through
+    let entry = audio_header::bgm_property_from_audio("bgm_custom", &idsp(32000, 48000, 0, 0)).unwrap();
+    assert_eq!((entry.loop_start_sample, entry.loop_end_sample, entry.loop_end_ms), (0, 48000, 1500));
+}
+
+#[test]
+fn lopus_samples_are_counted_from_packets() {
+    // 100 CELT packets of a 20ms frame, then one with three
+    let mut packets = vec![(0xF8, 0); 100];
+    packets.push((0xFB, 3));
+    let info = AudioInfo::parse(&lopus(&packets, 312)).unwrap();
+    assert_eq!(info.format, AudioFormat::Opus);
+    assert_eq!((info.channels, info.sample_rate), (2, 48000));
+    assert_eq!((info.total_samples, info.loop_points), (100 * 960 + 3 * 960 - 312, None));
+
+    let info = AudioInfo::parse(&lopus(&[(0x08, 0), (0x01, 0)], 0)).unwrap();
+    assert_eq!((info.total_samples, info.loop_points), (960 + 2 * 480, None));
+}
+
+#[test]
+fn namco_lopus_headers_hold_the_length_and_loop() {
+    let data = namco_lopus(98568, 48000, 90000, &vec![(0xF8, 0); 103], 312);
+    let info = AudioInfo::parse(&data).unwrap();
+    assert_eq!(info.format, AudioFormat::Opus);
+    assert_eq!((info.channels, info.sample_rate), (2, 48000));
+    assert_eq!((info.total_samples, info.loop_points), (98568, Some((48000, 90000))));
+
+    let entry = info.bgm_property("bgm_custom");
+    assert_eq!((entry.loop_start_ms, entry.loop_end_ms), (1000, 1875));
+    assert_eq!((entry.duration_sample, entry.duration_ms), (98568, 2054));
+
+    // Loops ending past the last sample end on it
+    let info = AudioInfo::parse(&namco_lopus(98568, 48000, 100000, &[(0xF8, 0)], 0)).unwrap();
+    assert_eq!(info.loop_points, Some((48000, 98568)));
+    let info = AudioInfo::parse(&namco_lopus(98568, 0, 0, &[(0xF8, 0)], 0)).unwrap();
+    assert_eq!(info.loop_points, None);
+    // What files that don't loop usually have
+    let info = AudioInfo::parse(&namco_lopus(98568, -1, -1, &[(0xF8, 0)], 0)).unwrap();
+    assert_eq!(info.loop_points, None);
+    let entry = info.bgm_property("bgm_custom");
+    assert_eq!((entry.loop_start_sample, entry.loop_end_sample), (0, 98568));
+
+    let mut data = namco_lopus(98568, 0, 0, &[(0xF8, 0)], 0);
+    data[0x30] = 0;
+    assert_eq!(AudioInfo::parse(&data), Err(AudioHeaderError::MissingChunk(0x80000001)));
+    data.truncate(0x30);
+    assert_eq!(AudioInfo::parse(&data), Err(AudioHeaderError::Truncated { offset: 0x30 }));
+}
+
+#[test]
+fn bad_headers_are_refused() {
+    assert_eq!(AudioInfo::parse(b"RIFF....").unwrap_err(), AudioHeaderError::UnknownFormat(b"RIFF".to_vec()));
+    assert_eq!(
+        AudioInfo::parse(&idsp(32000, 1000, 600, 500)).unwrap_err().to_string(),
+        "Your audio loops from sample 600 to 500, which doesn't fit in its 1000 samples!"
+    );
+    assert_eq!(AudioInfo::parse(&idsp(32000, 1000, 0, 2000)).unwrap().loop_points, Some((0, 1000)));
+    assert_eq!(AudioInfo::parse(&idsp(0, 1000, 0, 0)), Err(AudioHeaderError::ZeroSampleRate));
+
+    let mut data = lopus(&[(0xF8, 0)], 0);
+    data.truncate(data.len() - 1);
+    assert_eq!(AudioInfo::parse(&data), Err(AudioHeaderError::Truncated { offset: 0x38 }));
+    assert_eq!(AudioInfo::parse(&lopus(&[(0xFB, 0)], 0)), Err(AudioHeaderError::InvalidPacket { offset: 0x38 }));
+}
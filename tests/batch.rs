@@ -0,0 +1,182 @@
+#![cfg(feature = "mock")]
+
+use the_csk_collection_api::backend::mock::{self, with_mock};
+use the_csk_collection_api::batch::{Batch, BatchIssue};
+use the_csk_collection_api::convert;
+use the_csk_collection_api::registry::{self, Conflict, Table};
+use the_csk_collection_api::validate::DiagnosticKind;
+use the_csk_collection_api::{
+    hash40, AssignedInfoEntry, BgmDatabaseRootEntry, BgmPlaylistEntry, CharacterDatabaseEntry,
+    CharacterLayoutDatabaseEntry, Hash40, StreamPropertyEntry, StreamSetEntry,
+};
+
+// The vanilla rows references are checked against
+const VANILLA_PARAMS: &str = r#"<struct>
+  <list hash="db_root">
+    <struct index="0">
+      <hash40 hash="ui_bgm_id">ui_bgm_a01_smb_chijyou</hash40>
+      <hash40 hash="stream_set_id">set_a01_smb_chijyou</hash40>
+    </struct>
+    <struct index="1">
+      <hash40 hash="stream_set_id">set_a01_smb_chijyou</hash40>
+      <hash40 hash="info0">info_a01_smb_chijyou</hash40>
+    </struct>
+  </list>
+</struct>"#;
+
+// Every test uses its own names, the registry is shared between them
+fn song(name: &str) -> Batch {
+    registry::record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+    let mut batch = Batch::new();
+    batch
+        .add(StreamPropertyEntry::clone_from("stream_a01_smb_chijyou").stream_id(format!("stream_{}", name).as_str()))
+        .add(
+            AssignedInfoEntry::clone_from("info_a01_smb_chijyou")
+                .info_id(format!("info_{}", name).as_str())
+                .stream_id(format!("stream_{}", name).as_str()),
+        )
+        .add(
+            StreamSetEntry::clone_from("set_a01_smb_chijyou")
+                .stream_set_id(format!("set_{}", name).as_str())
+                .info0(format!("info_{}", name).as_str()),
+        )
+        .add(
+            BgmDatabaseRootEntry::clone_from("ui_bgm_a01_smb_chijyou")
+                .ui_bgm_id(format!("ui_bgm_{}", name).as_str())
+                .stream_set_id(format!("set_{}", name).as_str()),
+        );
+    batch.add_tracks_to_playlist(hash40("bgmmario").0, vec![BgmPlaylistEntry::new(format!("ui_bgm_{}", name).as_str())]);
+    batch
+}
+
+#[test]
+fn complete_batches_are_committed() {
+    mock::install();
+    song("batch_complete").commit().unwrap();
+
+    with_mock(|mock| {
+        let state = mock.state();
+        assert_eq!(
+            state.calls,
+            [
+                "add_stream_property_entry_info",
+                "add_assigned_info_entry_info",
+                "add_stream_set_entry_info",
+                "add_bgm_db_entry_info",
+                "add_tracks_to_playlist"
+            ]
+        );
+        assert_eq!(state.bgm_db[0].ui_bgm_id, hash40("ui_bgm_batch_complete").0);
+    });
+
+    // Entries added before or in the game can be referenced
+    let mut batch = Batch::new();
+    batch.add_tracks_to_playlist(
+        hash40("bgmzelda").0,
+        vec![BgmPlaylistEntry::new("ui_bgm_batch_complete"), BgmPlaylistEntry::new("ui_bgm_a01_smb_chijyou")],
+    );
+    batch.commit().unwrap();
+    let mut batch = Batch::new();
+    batch.add_tracks_to_playlist(hash40("bgmzelda").0, vec![BgmPlaylistEntry::new("ui_bgm_batch_unknown")]);
+    assert!(matches!(batch.check()[..], [BatchIssue::MissingReference { table: Table::Bgm, .. }]));
+}
+
+#[test]
+fn nothing_is_registered_on_failure() {
+    mock::install();
+    let mut batch = song("batch_failure");
+    batch.stream_set.clear();
+    batch.set_fighter_jingle(hash40("ui_chara_custom").0, "se_jingle\0custom");
+
+    let err = batch.commit().unwrap_err();
+    assert_eq!(
+        err.issues,
+        [
+            BatchIssue::MissingReference {
+                entry: "BgmDatabaseRootEntry",
+                id: hash40("ui_bgm_batch_failure"),
+                field: "stream_set_id",
+                table: Table::StreamSet,
+                reference: Hash40(hash40("set_batch_failure").0),
+            },
+            BatchIssue::NulInString { what: "fighter jingle", value: "se_jingle\0custom".to_string() },
+        ]
+    );
+    assert!(err.to_string().starts_with("The batch wasn't committed! Found 2 issue(s):"));
+    with_mock(|mock| assert!(mock.state().calls.is_empty()));
+
+    // The ids weren't taken either
+    song("batch_failure").commit().unwrap();
+}
+
+#[test]
+fn conflicts_and_errors_are_reported() {
+    mock::install();
+    let mut batch = song("batch_conflict");
+    batch.add(StreamPropertyEntry::clone_from("stream_a01_smb_chijyou").stream_id("stream_batch_conflict"));
+    batch.add(StreamSetEntry::new("set_batch_conflict_new"));
+
+    let issues = batch.check();
+    assert!(issues.contains(&BatchIssue::Conflict(Conflict::DuplicateId {
+        table: Table::StreamProperty,
+        id: hash40("stream_batch_conflict"),
+    })));
+    // Not cloned, so info0 is required
+    assert!(issues.iter().any(|issue| matches!(issue, BatchIssue::Invalid(diagnostic) if diagnostic.field == "info0")));
+    assert!(batch.commit().is_err());
+    with_mock(|mock| assert!(mock.state().calls.is_empty()));
+}
+
+#[test]
+fn clones_need_their_source_and_colors_their_layouts() {
+    mock::install();
+    let mut batch = song("batch_clone");
+    batch.bgm_db[0].clone_from_ui_bgm_id = Some(hash40("ui_bgm_batch_nowhere").0);
+    batch.add(CharacterDatabaseEntry::clone_from("ui_chara_mario").ui_chara_id("ui_chara_batch_clone").color_num(9));
+
+    let issues = batch.check();
+    assert_eq!(issues.len(), 2);
+    assert!(issues.contains(&BatchIssue::MissingReference {
+        entry: "BgmDatabaseRootEntry",
+        id: hash40("ui_bgm_batch_clone"),
+        field: "clone_from_ui_bgm_id",
+        table: Table::Bgm,
+        reference: hash40("ui_bgm_batch_nowhere"),
+    }));
+    assert!(issues.iter().any(|issue| matches!(
+        issue,
+        BatchIssue::Invalid(diagnostic) if matches!(&diagnostic.kind, DiagnosticKind::ColorsWithoutLayouts { missing, .. } if missing == &[8])
+    )));
+
+    batch.bgm_db[0].clone_from_ui_bgm_id = Some(hash40("ui_bgm_a01_smb_chijyou").0);
+    batch.add(
+        CharacterLayoutDatabaseEntry::clone_from("ui_chara_mario_00")
+            .ui_layout_id("ui_chara_batch_clone_08")
+            .ui_chara_id("ui_chara_batch_clone")
+            .chara_color(8),
+    );
+    assert_eq!(batch.check(), []);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn manifests_register_through_a_batch() {
+    use the_csk_collection_api::manifest::Manifest;
+
+    mock::install();
+    registry::record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+    let manifest = |clone_from: &str| {
+        Manifest::from_toml_str(&format!(
+            "[[bgm_db]]\nui_bgm_id = \"ui_bgm_batch_manifest\"\nclone_from_ui_bgm_id = \"{}\"",
+            clone_from
+        ))
+        .unwrap()
+    };
+
+    let err = manifest("ui_bgm_batch_manifest_nowhere").register().unwrap_err();
+    assert!(matches!(err.issues[..], [BatchIssue::MissingReference { field: "clone_from_ui_bgm_id", .. }]));
+    with_mock(|mock| assert!(mock.state().calls.is_empty()));
+
+    manifest("ui_bgm_a01_smb_chijyou").register().unwrap();
+    with_mock(|mock| assert_eq!(mock.state().calls, ["add_bgm_db_entry_info"]));
+}
@@ -0,0 +1,43 @@
+use the_csk_collection_api::{
+    hash40, BgmPlaylistEntry, BoolType, CharacterDatabaseEntry, Hash40, Hash40Type, ShortType, SignedByteType,
+    StageDatabaseEntry, StringType,
+};
+
+#[test]
+fn setters_overwrite() {
+    let chara = CharacterDatabaseEntry::clone_from("ui_chara_mario")
+        .ui_chara_id("ui_chara_custom")
+        .name_id("custom")
+        .fighter_kind(hash40("fighter_kind_mario"))
+        .ui_series_id("ui_series_mario")
+        .save_no(92)
+        .can_select(true);
+
+    assert_eq!(chara.ui_chara_id, hash40("ui_chara_custom").0);
+    assert_eq!(chara.clone_from_ui_chara_id, Some(hash40("ui_chara_mario").0));
+    assert_eq!(chara.name_id.value().and_then(|name| name.as_str()), Some("custom"));
+    assert!(matches!(chara.name_id, StringType::Overwrite(_)));
+    assert!(matches!(chara.fighter_kind, Hash40Type::Overwrite(hash) if hash == hash40("fighter_kind_mario").0));
+    assert!(matches!(chara.ui_series_id, Hash40Type::Overwrite(hash) if hash == hash40("ui_series_mario").0));
+    assert!(matches!(chara.save_no, SignedByteType::Overwrite(92)));
+    assert!(matches!(chara.can_select, BoolType::Overwrite(true)));
+    assert!(!chara.disp_order.is_set());
+
+    // Labels passed to setters can be printed back
+    assert_eq!(Hash40(chara.ui_chara_id).to_string(), "ui_chara_custom");
+}
+
+#[test]
+fn constructors() {
+    let stage = StageDatabaseEntry::new("ui_stage_custom").name_id(String::from("custom"));
+    assert_eq!(stage.ui_stage_id, hash40("ui_stage_custom").0);
+    assert_eq!(stage.clone_from_ui_stage_id, None);
+
+    let playlist = BgmPlaylistEntry::new(hash40("ui_bgm_custom")).order0(3).incidence0(500);
+    assert_eq!(playlist.ui_bgm_id, hash40("ui_bgm_custom").0);
+    assert!(matches!(playlist.order0, ShortType::Overwrite(3)));
+
+    // The field's own type is passed through as is
+    let playlist = playlist.order0(ShortType::Optional(Some(4)));
+    assert!(matches!(playlist.order0, ShortType::Optional(Some(4))));
+}
@@ -0,0 +1,68 @@
+use the_csk_collection_api::chara::{IndexKey, LabelKey, SlotOutOfRange, MAX_COLOR_SLOTS};
+use the_csk_collection_api::{hash40, CharacterDatabaseEntry, UnsignedByteMap, UnsignedByteType};
+
+#[test]
+fn keys_match_the_param_labels() {
+    assert_eq!(IndexKey::ColorIndex.label(0), "c00_index");
+    assert_eq!(IndexKey::NarrationIndex.key(7), hash40("n07_index").0);
+    assert_eq!(IndexKey::ColorGroup.key(12), hash40("c12_group").0);
+    assert_eq!(LabelKey::CharacallLabel.key(3), hash40("characall_label_c03").0);
+    assert_eq!(LabelKey::CharacallLabelArticle.key(3), hash40("characall_label_article_c03").0);
+    // Up to the last slot color_num can reach
+    assert_eq!(IndexKey::ColorIndex.label(100), "c100_index");
+    assert_eq!(LabelKey::CharacallLabel.key(u8::MAX), hash40("characall_label_c255").0);
+}
+
+#[test]
+fn setters_fill_the_maps() {
+    let mut chara = CharacterDatabaseEntry::default();
+    chara.set_color_index(0, 4).unwrap();
+    chara.set_narration_index(1, 2).unwrap();
+    chara.set_color_group(1, 0).unwrap();
+    chara.set_characall_label(1, hash40("vc_narration_characall_custom").0).unwrap();
+    chara.set_characall_label_article(1, hash40("vc_narration_characall_custom_article").0).unwrap();
+
+    let UnsignedByteMap::Overwrite(map) = &chara.extra_index_maps else { panic!("index map wasn't created") };
+    assert!(matches!(map[&hash40("c00_index").0], UnsignedByteType::Overwrite(4)));
+    assert_eq!(map.len(), 3);
+    assert_eq!(chara.color_index(0), Some(4));
+    assert_eq!(chara.narration_index(1), Some(2));
+    assert_eq!(chara.color_group(1), Some(0));
+    assert_eq!(chara.color_index(1), None);
+    assert_eq!(chara.characall_label(1), Some(hash40("vc_narration_characall_custom").0));
+    assert_eq!(chara.characall_label_article(1), Some(hash40("vc_narration_characall_custom_article").0));
+
+    // Setting a slot again replaces it
+    chara.set_color_index(0, 5).unwrap();
+    assert_eq!(chara.color_index(0), Some(5));
+}
+
+#[test]
+fn setters_leave_the_label_table_alone() {
+    let mut chara = CharacterDatabaseEntry::default();
+    chara.set_color_index(42, 1).unwrap();
+    chara.set_characall_label_article(42, hash40("vc_narration_characall_custom").0).unwrap();
+    assert_eq!(chara.color_index(42), Some(1));
+    assert_eq!(hash40("c42_index").label(), None);
+    assert_eq!(hash40("characall_label_article_c42").label(), None);
+}
+
+#[test]
+fn setters_refuse_slots_past_the_colors() {
+    let mut chara = CharacterDatabaseEntry::default();
+    chara.set_color_index(MAX_COLOR_SLOTS - 1, 1).unwrap();
+    let err = chara.set_color_index(100, 1).unwrap_err();
+    assert_eq!(err, SlotOutOfRange { slot: 100, slots: MAX_COLOR_SLOTS });
+    assert_eq!(err.to_string(), "Color slot 100 is out of range, the entry only has 100 color slots!");
+    assert_eq!(chara.color_index(100), None);
+
+    // An entry's own color_num is the limit when it sets one
+    let mut chara = CharacterDatabaseEntry { color_num: UnsignedByteType::Overwrite(10), ..Default::default() };
+    chara.set_narration_index(9, 2).unwrap();
+    assert_eq!(chara.set_narration_index(10, 2), Err(SlotOutOfRange { slot: 10, slots: 10 }));
+    assert_eq!(chara.set_color_group(10, 0), Err(SlotOutOfRange { slot: 10, slots: 10 }));
+    assert_eq!(chara.set_characall_label(10, 0), Err(SlotOutOfRange { slot: 10, slots: 10 }));
+    assert_eq!(chara.set_characall_label_article(10, 0), Err(SlotOutOfRange { slot: 10, slots: 10 }));
+    assert_eq!(chara.narration_index(10), None);
+    assert!(chara.extra_hash_maps.value().is_none());
+}
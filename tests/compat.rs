@@ -0,0 +1,27 @@
+use the_csk_collection_api::compat::Api;
+use the_csk_collection_api::Version;
+
+#[test]
+fn versions_parse_and_print() {
+    assert_eq!("1.2.3".parse(), Ok(Version::new(1, 2, 3)));
+    assert_eq!("v1.4".parse(), Ok(Version::new(1, 4, 0)));
+    assert!("1".parse::<Version>().is_err());
+    assert!("1.2.3.4".parse::<Version>().is_err());
+    assert!("1.x.3".parse::<Version>().is_err());
+    assert_eq!(Version::new(1, 10, 2).to_string(), "1.10.2");
+}
+
+#[test]
+fn versions_order_by_significance() {
+    assert!(Version::new(1, 10, 0) > Version::new(1, 9, 9));
+    assert!(Version::new(2, 0, 0) > Version::new(1, 99, 99));
+    assert!(Version::new(1, 2, 1) > Version::new(1, 2, 0));
+}
+
+#[test]
+fn every_api_has_a_name() {
+    for api in Api::ALL {
+        assert!(api.name().starts_with("add_"));
+        assert_eq!(api.to_string(), api.name());
+    }
+}
@@ -0,0 +1,114 @@
+use the_csk_collection_api::convert::{self, ConvertError, EntryKind, ParamEntry, ParamValue};
+use the_csk_collection_api::{hash40, CharacterDatabaseEntry, Hash40, SignedByteType, StringType, UnsignedByteMap};
+
+// Two rows of ui_chara_db.prc as paracobNET exports it, trimmed to a few fields each
+const UI_CHARA_DB: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<struct>
+  <list hash="db_root">
+    <struct index="0">
+      <hash40 hash="ui_chara_id">ui_chara_mario</hash40>
+      <hash40 hash="fighter_kind">fighter_kind_mario</hash40>
+      <hash40 hash="fighter_kind_corps">dummy</hash40>
+      <hash40 hash="ui_series_id">ui_series_mario</hash40>
+      <hash40 hash="fighter_type">fighter_type_normal</hash40>
+      <hash40 hash="alt_chara_id">dummy</hash40>
+      <hash40 hash="shop_item_tag">dummy</hash40>
+      <string hash="name_id">mario</string>
+      <short hash="exhibit_year">1981</short>
+      <sbyte hash="disp_order">0</sbyte>
+      <sbyte hash="save_no">0</sbyte>
+      <bool hash="can_select">True</bool>
+      <byte hash="color_num">8</byte>
+      <byte hash="c00_index">0</byte>
+      <hash40 hash="characall_label_c00">vc_narration_characall_mario</hash40>
+    </struct>
+    <struct index="1">
+      <hash40 hash="ui_chara_id">ui_chara_donkey</hash40>
+      <hash40 hash="fighter_kind">fighter_kind_donkey</hash40>
+      <bool hash="0x0d3ac3ff3b">False</bool>
+      <string hash="name_id" />
+    </struct>
+  </list>
+</struct>
+"#;
+
+#[test]
+fn ui_chara_db_rows_parse() {
+    let rows = convert::parse_rows(UI_CHARA_DB).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].fields.len(), 15);
+    assert_eq!(rows[0].get("ui_chara_id"), Some(&ParamValue::Hash40(hash40("ui_chara_mario"))));
+    assert_eq!(rows[0].get("name_id"), Some(&ParamValue::String("mario".to_string())));
+    assert_eq!(rows[0].get("can_select"), Some(&ParamValue::Bool(true)));
+    assert_eq!(rows[0].get("exhibit_year"), Some(&ParamValue::Short(1981)));
+
+    // Self closing value nodes are empty values
+    assert_eq!(rows[1].fields.last(), Some(&(hash40("name_id"), ParamValue::String(String::new()))));
+    assert_eq!(rows[1].fields[2], (Hash40(0x0D3AC3FF3B), ParamValue::Bool(false)));
+
+    // Labels are collected, not added to the label table
+    assert!(rows[0].labels.contains(&"ui_chara_mario".to_string()));
+    assert!(rows[0].labels.contains(&"characall_label_c00".to_string()));
+    assert!(!rows[1].labels.contains(&"0x0d3ac3ff3b".to_string()));
+    assert_eq!(hash40("vc_narration_characall_mario").label(), None);
+}
+
+#[test]
+fn entry_kinds_are_detected() {
+    let rows = convert::parse_rows(UI_CHARA_DB).unwrap();
+    assert_eq!(EntryKind::detect(&rows[0]), Some(EntryKind::Chara));
+
+    let detect = |xml: &str| EntryKind::detect(&convert::parse_rows(xml).unwrap()[0]);
+    assert_eq!(
+        detect(r#"<hash40 hash="ui_bgm_id">ui_bgm_a01_smb_chijyou</hash40><hash40 hash="stream_set_id">set_a01_smb_chijyou</hash40>"#),
+        Some(EntryKind::Bgm)
+    );
+    assert_eq!(detect(r#"<hash40 hash="ui_bgm_id">ui_bgm_a01_smb_chijyou</hash40>"#), Some(EntryKind::BgmPlaylist));
+    assert_eq!(detect(r#"<hash40 hash="ui_layout_id">ui_chara_mario_00</hash40>"#), Some(EntryKind::CharaLayout));
+    assert_eq!(detect(r#"<int hash="unknown">1</int>"#), None);
+
+    assert_eq!("chara_layout".parse(), Ok(EntryKind::CharaLayout));
+    assert_eq!("charas".parse::<EntryKind>(), Err(ConvertError::UnknownEntryKind("charas".to_string())));
+}
+
+#[test]
+fn rows_convert_to_entries_and_rust() {
+    let rows = convert::parse_rows(UI_CHARA_DB).unwrap();
+    let entry = CharacterDatabaseEntry::from_row(&rows[0]).unwrap();
+    assert_eq!(entry.ui_chara_id, hash40("ui_chara_mario").0);
+    assert!(matches!(&entry.name_id, StringType::Overwrite(name) if name.as_str() == Some("mario")));
+    // disp_order is written as Optional
+    assert!(matches!(entry.disp_order, SignedByteType::Optional(Some(0))));
+    assert!(matches!(&entry.extra_index_maps, UnsignedByteMap::Overwrite(map) if map.len() == 1));
+
+    let sources = convert::rows_to_rust(EntryKind::Chara, &rows).unwrap();
+    assert_eq!(sources.len(), 2);
+    assert!(sources[0].starts_with("the_csk_collection_api::CharacterDatabaseEntry {\n"));
+    assert!(sources[0].contains("    ui_chara_id: 0x0edaf3c863,\n"));
+    assert!(sources[0].contains(
+        "    name_id: the_csk_collection_api::StringType::Overwrite(the_csk_collection_api::CStrCSK::new(\"mario\")),\n"
+    ));
+    assert!(sources[0].contains("    disp_order: the_csk_collection_api::SignedByteType::Optional(Some(0)),\n"));
+    assert!(sources[0].ends_with("    ..Default::default()\n}"));
+    assert!(sources[1].contains("CStrCSK::new(\"\")"));
+}
+
+#[test]
+fn bad_rows_are_errors() {
+    assert_eq!(
+        convert::parse_rows(r#"<struct><byte hash="color_num">300</byte></struct>"#),
+        Err(ConvertError::InvalidValue { field: "color_num".to_string(), value: "300".to_string() })
+    );
+    assert_eq!(
+        convert::parse_rows("<struct>\n<vector hash=\"pos\">1</vector></struct>"),
+        Err(ConvertError::UnknownType { line: 2, node: "vector".to_string() })
+    );
+    let err = convert::parse_rows("<struct>\n\n<byte hash=\"c00_index\">0</short></struct>").unwrap_err();
+    assert_eq!(err, ConvertError::Xml { line: 3, message: "Expected </byte>, found </short>".to_string() });
+    assert_eq!(err.to_string(), "Failed parsing the XML on line 3! Reason: Expected </byte>, found </short>");
+    assert!(matches!(convert::parse_rows(r#"<hash40 hash="0xzz">dummy</hash40>"#), Err(ConvertError::Xml { line: 1, .. })));
+
+    // Rows of the wrong kind still fail on their values
+    let rows = convert::parse_rows(r#"<hash40 hash="name_id">mario</hash40>"#).unwrap();
+    assert!(matches!(convert::rows_to_rust(EntryKind::Chara, &rows), Err(ConvertError::InvalidValue { .. })));
+}
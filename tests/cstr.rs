@@ -0,0 +1,70 @@
+// Written to be run under Miri as well (`cargo +nightly miri test --test cstr`), which fails on
+// any leak, double free or use after free.
+use the_csk_collection_api::{CStrCSK, CharacterDatabaseEntry, StringType};
+
+#[test]
+fn clones_are_deep_copies() {
+    let original = CStrCSK::new("mario");
+    let clone = original.clone();
+    assert_ne!(original.as_ptr(), clone.as_ptr());
+    assert_eq!(original, clone);
+    drop(original);
+    assert_eq!(clone.as_str(), Some("mario"));
+}
+
+#[test]
+fn get_on_clones_frees_each_once() {
+    let original = CStrCSK::new("luigi");
+    let clone = original.clone();
+    assert_eq!(original.get().as_deref(), Some("luigi"));
+    assert_eq!(clone.get().as_deref(), Some("luigi"));
+}
+
+#[test]
+fn set_replaces_the_old_string() {
+    let value = CStrCSK::new("peach").set("daisy").set("rosalina");
+    assert_eq!(value.as_str(), Some("rosalina"));
+}
+
+#[test]
+fn raw_round_trip() {
+    let ptr = CStrCSK::new("yoshi").into_raw();
+    let value = unsafe { CStrCSK::from_raw(ptr) };
+    assert_eq!(value.as_str(), Some("yoshi"));
+
+    let empty = unsafe { CStrCSK::from_raw(CStrCSK::empty().into_raw()) };
+    assert!(empty.is_empty());
+    assert_eq!(empty.get(), None);
+}
+
+// Kept public for code written before the accessors
+#[test]
+#[allow(deprecated)]
+fn ptr_field_still_reads() {
+    let value = CStrCSK::new("wario");
+    assert_eq!(value.ptr as *const i8, value.as_ptr());
+    assert!(CStrCSK::empty().ptr.is_null());
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(CStrCSK::from("kirby").as_str(), Some("kirby"));
+    assert_eq!(CStrCSK::from(String::from("fox")).as_str(), Some("fox"));
+    assert_eq!(CStrCSK::from(std::ffi::CString::new("ness").unwrap()).as_str(), Some("ness"));
+    assert_eq!(format!("{:?}", CStrCSK::from("samus")), "CStrCSK(\"samus\")");
+    assert_eq!(format!("{:?}", CStrCSK::empty()), "CStrCSK(null)");
+}
+
+#[test]
+#[should_panic]
+fn interior_nul_panics() {
+    let _ = CStrCSK::from("link\0zelda");
+}
+
+#[test]
+fn entries_own_their_strings() {
+    let entry = CharacterDatabaseEntry { name_id: StringType::Overwrite(CStrCSK::new("custom")), ..Default::default() };
+    let clone = entry.clone();
+    drop(entry);
+    assert!(matches!(&clone.name_id, StringType::Overwrite(name) if name.as_str() == Some("custom")));
+}
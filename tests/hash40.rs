@@ -0,0 +1,53 @@
+use the_csk_collection_api::hash40::{add_label, load_labels_from_str, ParseHash40Error};
+use the_csk_collection_api::{hash40, Hash40};
+
+// The label table is shared by every test in this file, so each test uses its own labels
+
+#[test]
+fn hashes_match_known_values() {
+    // The length in the upper byte, the CRC32 check value in the lower four
+    assert_eq!(hash40("123456789"), Hash40(0x09CBF43926));
+    assert_eq!(hash40("ui_chara_mario"), Hash40(0x0EDAF3C863));
+    assert_eq!(hash40("fighter_kind_mario"), Hash40(0x1254206CE1));
+    assert_eq!(hash40(""), Hash40(0));
+
+    const MARIO: Hash40 = Hash40::new("ui_chara_mario");
+    assert_eq!(MARIO.as_u64(), 0x0EDAF3C863);
+}
+
+#[test]
+fn hashes_parse_from_hex_or_labels() {
+    assert_eq!("0x0edaf3c863".parse(), Ok(Hash40(0x0EDAF3C863)));
+    assert_eq!("0X0EDAF3C863".parse(), Ok(Hash40(0x0EDAF3C863)));
+    assert_eq!("ui_chara_mario".parse(), Ok(Hash40(0x0EDAF3C863)));
+    assert_eq!("0xnothex".parse::<Hash40>(), Err(ParseHash40Error("0xnothex".to_string())));
+}
+
+#[test]
+fn hashes_print_their_label_once_known() {
+    let hash = hash40("ui_chara_test_print");
+    assert_eq!(hash.label(), None);
+    assert_eq!(hash.to_string(), format!("{:#012x}", hash.0));
+    assert_eq!(format!("{:?}", hash), format!("Hash40({:#012x})", hash.0));
+
+    assert_eq!(add_label("ui_chara_test_print"), hash);
+    assert_eq!(hash.to_string(), "ui_chara_test_print");
+    assert_eq!(format!("{:?}", hash), "Hash40(\"ui_chara_test_print\")");
+}
+
+#[test]
+fn labels_load_from_param_labels() {
+    let count = load_labels_from_str(
+        "0x0a00000001,ui_chara_test_lower\n\
+         0X0A00000002 , ui_chara_test_upper\n\
+         \n\
+         ui_chara_test_plain\n\
+         0xnothex,ui_chara_test_skipped\n\
+         nohex,ui_chara_test_skipped",
+    );
+    assert_eq!(count, 3);
+    assert_eq!(Hash40(0x0A00000001).label().as_deref(), Some("ui_chara_test_lower"));
+    assert_eq!(Hash40(0x0A00000002).label().as_deref(), Some("ui_chara_test_upper"));
+    assert_eq!(hash40("ui_chara_test_plain").label().as_deref(), Some("ui_chara_test_plain"));
+    assert_eq!(hash40("ui_chara_test_skipped").label(), None);
+}
@@ -0,0 +1,63 @@
+use the_csk_collection_api::convert::{ParamFields, ParamValue};
+use the_csk_collection_api::layout::{CharacterLayout, PortraitKind, Vec2};
+use the_csk_collection_api::{hash40, CharacterLayoutDatabaseEntry, FloatType, UnsignedByteType};
+
+// Every field set to a value of its own, half of them as Optional
+fn filled_entry() -> CharacterLayoutDatabaseEntry {
+    let mut entry = CharacterLayoutDatabaseEntry::default();
+    for (index, field) in CharacterLayoutDatabaseEntry::FIELDS.iter().enumerate() {
+        let values = [
+            ParamValue::Float(index as f32 + 0.5),
+            ParamValue::Byte(index as u8),
+            ParamValue::Bool(index % 3 == 0),
+            ParamValue::Hash40(hash40(field)),
+        ];
+        let set = values.iter().any(|value| entry.set_field(field, value, index % 2 == 0).unwrap_or(false));
+        assert!(set, "{} couldn't be set", field);
+    }
+    entry
+}
+
+#[test]
+fn conversion_is_lossless() {
+    let entry = filled_entry();
+    let before = entry.rust_fields();
+    let layout = CharacterLayout::from(entry);
+    assert_eq!(layout.portraits.len(), PortraitKind::ALL.len());
+    let after = CharacterLayoutDatabaseEntry::from(layout).rust_fields();
+    assert_eq!(before, after);
+
+    // Unset fields stay unset
+    let layout = CharacterLayout::from(CharacterLayoutDatabaseEntry::default());
+    let unset = CharacterLayoutDatabaseEntry::default().rust_fields();
+    assert_eq!(CharacterLayoutDatabaseEntry::from(layout).rust_fields(), unset);
+}
+
+#[test]
+fn fields_land_in_the_right_place() {
+    let entry = CharacterLayoutDatabaseEntry {
+        eye_1_flash3_pos_x: FloatType::Overwrite(4.0),
+        eye_1_flash3_pos_y: FloatType::Optional(Some(-2.0)),
+        eye_2_flash_count: UnsignedByteType::Overwrite(2),
+        chara_3_6_scale: FloatType::Overwrite(1.25),
+        chara_7_1_offset_y: FloatType::Overwrite(30.0),
+        chara_1_offset_x: FloatType::Overwrite(7.0),
+        ..Default::default()
+    };
+    let mut layout = CharacterLayout::from(entry);
+    assert_eq!(layout.eyes[1].positions[3].get(), Some((4.0, -2.0)));
+    assert!(matches!(layout.eyes[2].count, UnsignedByteType::Overwrite(2)));
+    assert!(matches!(layout.portrait(PortraitKind::Chara3(6)).scale, FloatType::Overwrite(scale) if scale == 1.25));
+    assert!(matches!(layout.portrait(PortraitKind::Chara7(1)).offset.y, FloatType::Overwrite(y) if y == 30.0));
+    assert!(matches!(layout.portrait(PortraitKind::Chara1(0)).offset.x, FloatType::Overwrite(x) if x == 7.0));
+
+    layout.portrait_mut(PortraitKind::SelectIconList).unwrap().offset = Vec2::new(1.0, 2.0);
+    assert!(layout.portrait_mut(PortraitKind::Chara3(8)).is_none());
+    assert!(!layout.portraits.contains_key(&PortraitKind::Chara3(8)));
+    let entry = CharacterLayoutDatabaseEntry::from(layout);
+    assert!(matches!(entry.chara_select_icon_list_offset_x, FloatType::Overwrite(x) if x == 1.0));
+    assert!(matches!(entry.chara_select_icon_list_offset_y, FloatType::Overwrite(y) if y == 2.0));
+    assert!(!PortraitKind::Chara3(8).is_valid());
+    assert_eq!(PortraitKind::Chara1(0).to_string(), "chara_1");
+    assert_eq!(PortraitKind::Chara3(6).to_string(), "chara_3_6");
+}
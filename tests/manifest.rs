@@ -0,0 +1,87 @@
+#![cfg(feature = "serde")]
+
+use the_csk_collection_api::manifest::{Manifest, ManifestError};
+use the_csk_collection_api::{hash40, Hash40Type, SignedByteType, StringType};
+
+const MANIFEST: &str = r#"
+narration_characall = ["vc_narration_characall_manifest"]
+online_charas = ["ui_chara_manifest"]
+
+[[chara_db]]
+ui_chara_id = "ui_chara_manifest"
+clone_from_ui_chara_id = "0x0edaf3c863"
+name_id = "manifest"
+ui_series_id = 0x0f1b4b6a62
+disp_order = { optional = 90 }
+
+[[fighter_jingles]]
+ui_chara_id = "ui_chara_manifest"
+jingle = "se_jingle_manifest"
+
+[[playlists]]
+playlist = "bgmmario"
+tracks = [{ ui_bgm_id = "ui_bgm_manifest", incidence0 = 500 }]
+"#;
+
+#[test]
+fn hashes_read_as_labels_hex_or_integers() {
+    let manifest = Manifest::from_toml_str(MANIFEST).unwrap();
+    let entry = &manifest.chara_db[0];
+    assert_eq!(entry.ui_chara_id, hash40("ui_chara_manifest").0);
+    assert_eq!(entry.clone_from_ui_chara_id, Some(hash40("ui_chara_mario").0));
+    assert!(matches!(entry.ui_series_id, Hash40Type::Overwrite(0x0F1B4B6A62)));
+    assert!(matches!(&entry.name_id, StringType::Overwrite(name) if name.as_str() == Some("manifest")));
+    assert!(matches!(entry.disp_order, SignedByteType::Optional(Some(90))));
+    assert!(matches!(entry.save_no, SignedByteType::Optional(None)));
+    assert_eq!(manifest.online_charas, [hash40("ui_chara_manifest")]);
+    assert_eq!(manifest.playlists[0].playlist, hash40("bgmmario"));
+    assert_eq!(manifest.fighter_jingles[0].jingle, "se_jingle_manifest");
+}
+
+#[test]
+fn manifests_round_trip() {
+    let manifest = Manifest::from_toml_str(MANIFEST).unwrap();
+    manifest.add_labels();
+
+    let toml = manifest.to_toml_string().unwrap();
+    // Labels read from the manifest are written back as labels
+    assert!(toml.contains("ui_chara_id = \"ui_chara_manifest\""));
+    assert!(toml.contains("[chara_db.disp_order]\noptional = 90\n"));
+    // Hashes without a known label are written as hex
+    assert!(toml.contains("ui_series_id = \"0x0f1b4b6a62\""));
+    assert!(!toml.contains("save_no"));
+    assert_eq!(Manifest::from_toml_str(&toml).unwrap().to_toml_string().unwrap(), toml);
+
+    let json = manifest.to_json_string().unwrap();
+    let from_json = Manifest::from_json_str(&json).unwrap();
+    assert_eq!(from_json.to_json_string().unwrap(), json);
+    assert_eq!(from_json.to_toml_string().unwrap(), toml);
+}
+
+#[test]
+fn reading_adds_no_labels() {
+    let text = "online_charas = [\"ui_chara_unlabeled\", \"0x0edaf3c863\"]";
+    let manifest = Manifest::from_toml_str(text).unwrap();
+    assert_eq!(manifest.labels, ["ui_chara_unlabeled"]);
+    assert_eq!(manifest.online_charas[0].label(), None);
+    assert_eq!(manifest.to_toml_string().unwrap(), "online_charas = [\"0x123c8695e1\", \"0x0edaf3c863\"]\n");
+
+    manifest.add_labels();
+    assert_eq!(manifest.online_charas[0].label().as_deref(), Some("ui_chara_unlabeled"));
+}
+
+#[test]
+fn bad_manifests_are_refused() {
+    let nul = "narration_characall = [\"vc_narration\\u0000characall\"]";
+    assert!(matches!(Manifest::from_toml_str(nul), Err(ManifestError::Toml(err)) if err.to_string().contains("NUL")));
+    let nul = r#"{ "fighter_jingles": [{ "ui_chara_id": "ui_chara_manifest", "jingle": "se_\u0000jingle" }] }"#;
+    assert!(matches!(Manifest::from_json_str(nul), Err(ManifestError::Json(err)) if err.to_string().contains("NUL")));
+
+    assert!(Manifest::from_toml_str("[[chara_db]]\nui_chara_id = \"ui_chara_manifest\"\nname = \"typo\"").is_err());
+    assert!(Manifest::from_toml_str("[[chara_db]]\nui_chara_id = \"0xnothex\"").is_err());
+    // Every other field can be left out, but not the id
+    let missing_id = Manifest::from_toml_str("[[chara_db]]\nname_id = \"manifest\"").unwrap_err();
+    assert!(missing_id.to_string().contains("missing field `ui_chara_id`"), "{}", missing_id);
+    assert!(Manifest::from_json_str(r#"{ "bgm_db": [{ "name_id": "manifest" }] }"#).is_err());
+    assert!(matches!(Manifest::from_file("manifest.yaml"), Err(ManifestError::UnknownFormat(_))));
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "mock")]
+
+use the_csk_collection_api::backend::mock::{self, with_mock};
+use the_csk_collection_api::{hash40, CStrCSK, CharacterDatabaseEntry, StringType};
+
+#[test]
+fn calls_are_recorded() {
+    mock::install();
+    the_csk_collection_api::play_bgm(hash40("ui_bgm_custom").0);
+    the_csk_collection_api::allow_ui_chara_hash_online(hash40("ui_chara_custom").0);
+    the_csk_collection_api::add_narration_characall_entry("vc_narration_characall_custom");
+    the_csk_collection_api::set_fighter_jingle(hash40("ui_chara_custom").0, "se_jingle_custom");
+
+    with_mock(|mock| {
+        let state = mock.state();
+        assert_eq!(
+            state.calls,
+            ["play_bgm", "allow_ui_chara_hash_online", "add_narration_characall_entry", "set_fighter_jingle"]
+        );
+        assert_eq!(state.playing_bgm, Some(hash40("ui_bgm_custom").0));
+        assert!(state.online_charas.contains(&hash40("ui_chara_custom").0));
+        assert_eq!(state.narration_characall, ["vc_narration_characall_custom"]);
+        assert_eq!(state.fighter_jingles[&hash40("ui_chara_custom").0], "se_jingle_custom");
+    });
+}
+
+#[test]
+fn entries_are_stored() {
+    mock::install();
+    the_csk_collection_api::add_chara_db_entry_info(CharacterDatabaseEntry {
+        ui_chara_id: hash40("ui_chara_custom").0,
+        clone_from_ui_chara_id: Some(hash40("ui_chara_mario").0),
+        name_id: StringType::Overwrite(CStrCSK::new("custom")),
+        ..Default::default()
+    });
+
+    with_mock(|mock| {
+        let state = mock.state();
+        assert_eq!(state.chara_db.len(), 1);
+        assert_eq!(state.chara_db[0].ui_chara_id, hash40("ui_chara_custom").0);
+        assert!(matches!(&state.chara_db[0].name_id, StringType::Overwrite(name) if name.as_str() == Some("custom")));
+    });
+}
+
+#[test]
+fn getters_read_the_state() {
+    mock::install();
+    with_mock(|mock| {
+        let mut state = mock.state_mut();
+        state.online = true;
+        state.victor_color = 3;
+    });
+    assert!(the_csk_collection_api::is_online());
+    the_csk_collection_api::change_entry_chara_ui(2, hash40("ui_chara_custom").0, 5);
+    assert_eq!(the_csk_collection_api::get_ui_chara_from_entry_id(2), hash40("ui_chara_custom").0);
+    assert_eq!(the_csk_collection_api::get_color_from_entry_id(2), 5);
+
+    with_mock(|mock| mock.reset());
+    assert!(!the_csk_collection_api::is_online());
+}
+
+#[test]
+fn missing_plugins_are_refused() {
+    mock::install();
+    use the_csk_collection_api::compat::{self, Api, CompatError};
+    use the_csk_collection_api::{MiiHatDatabaseEntry, Version};
+
+    // Functions without a known minimum version work with any installed plugin
+    with_mock(|mock| mock.state_mut().version = Some(Version::new(1, 3, 9)));
+    assert_eq!(Api::NarrationCharacall.min_version(), None);
+    assert_eq!(compat::try_add_narration_characall_entry("vc_narration_characall_custom"), Ok(true));
+
+    // The newer tables are refused before the plugin is called
+    assert_eq!(Api::MiiHatDb.min_version(), Some(Version::new(1, 4, 0)));
+    let err = compat::try_add_mii_hat_db_entry_info(&MiiHatDatabaseEntry::default()).unwrap_err();
+    assert_eq!(
+        err,
+        CompatError::Unsupported { api: Api::MiiHatDb, needed: Version::new(1, 4, 0), found: Version::new(1, 3, 9) }
+    );
+    assert_eq!(
+        err.to_string(),
+        "add_mii_hat_db_entry_info needs CSK Collection 1.4.0 or newer, but 1.3.9 is installed!"
+    );
+    with_mock(|mock| assert!(mock.state().mii_hat_db.is_empty()));
+
+    with_mock(|mock| mock.state_mut().version = Some(Version::new(1, 4, 0)));
+    assert_eq!(compat::try_add_mii_hat_db_entry_info(&MiiHatDatabaseEntry::default()), Ok(()));
+    with_mock(|mock| assert_eq!(mock.state().mii_hat_db.len(), 1));
+
+    with_mock(|mock| mock.state_mut().version = None);
+    assert_eq!(the_csk_collection_api::get_plugin_version(), None);
+    assert_eq!(compat::check(Api::CharaDb), Err(CompatError::PluginMissing));
+    assert_eq!(
+        compat::try_add_mii_hat_db_entry_info(&MiiHatDatabaseEntry::default()),
+        Err(CompatError::PluginMissing)
+    );
+}
+
+#[test]
+fn fighter_bundle_registers_everything() {
+    mock::install();
+    use the_csk_collection_api::batch::BatchIssue;
+    use the_csk_collection_api::registry::{Conflict, Table};
+    use the_csk_collection_api::{BundleError, FighterBundle, Hash40Type, UnsignedByteType};
+
+    let entries = FighterBundle::new("bundled")
+        .clone_from("mario")
+        .cloned_layouts(8)
+        .narration()
+        .jingle("se_jingle_bundled")
+        .allow_online()
+        .register()
+        .unwrap();
+    assert_eq!(entries.chara.ui_chara_id, hash40("ui_chara_bundled").0);
+    assert!(matches!(entries.chara.color_num, UnsignedByteType::Overwrite(8)));
+
+    with_mock(|mock| {
+        let state = mock.state();
+        assert_eq!(state.chara_db.len(), 1);
+        assert_eq!(state.chara_db[0].clone_from_ui_chara_id, Some(hash40("ui_chara_mario").0));
+        assert_eq!(state.chara_layout_db.len(), 8);
+        for (color, layout) in state.chara_layout_db.iter().enumerate() {
+            assert_eq!(layout.ui_layout_id, hash40(&format!("ui_layout_bundled_{:02}", color)).0);
+            assert_eq!(layout.clone_from_ui_layout_id, Some(hash40(&format!("ui_layout_mario_{:02}", color)).0));
+            assert!(matches!(layout.ui_chara_id, Hash40Type::Overwrite(id) if id == hash40("ui_chara_bundled").0));
+        }
+        assert_eq!(state.narration_characall, ["vc_narration_characall_bundled"]);
+        assert_eq!(state.fighter_jingles[&hash40("ui_chara_bundled").0], "se_jingle_bundled");
+        assert!(state.online_charas.contains(&hash40("ui_chara_bundled").0));
+        assert_eq!(state.calls[0], "add_chara_db_entry_info");
+    });
+
+    let chara = CharacterDatabaseEntry { color_num: UnsignedByteType::Overwrite(8), ..Default::default() };
+    assert_eq!(
+        FighterBundle::new("custom").chara(chara).cloned_layouts(2).register().unwrap_err(),
+        BundleError::ColorNumMismatch { color_num: 8, layouts: 2 }
+    );
+    assert_eq!(FighterBundle::new("custom").register().unwrap_err(), BundleError::NoLayouts);
+
+    // Bundles go through the same checks as any batch, so the same fighter can't be registered twice
+    let err = FighterBundle::new("bundled").cloned_layouts(1).register().unwrap_err();
+    let BundleError::Batch(err) = err else { panic!("expected a batch error, got {:?}", err) };
+    let conflict = Conflict::DuplicateId { table: Table::Chara, id: hash40("ui_chara_bundled") };
+    assert!(err.issues.contains(&BatchIssue::Conflict(conflict)), "{}", err);
+    with_mock(|mock| assert_eq!(mock.state().chara_db.len(), 1));
+}
+
+#[test]
+fn fighter_bundles_take_up_to_255_layouts() {
+    use the_csk_collection_api::{BundleError, FighterBundle, UnsignedByteType};
+
+    let entries = FighterBundle::new("custom").cloned_layouts(255).entries().unwrap();
+    assert!(matches!(entries.chara.color_num, UnsignedByteType::Overwrite(255)));
+    assert_eq!(entries.layouts.len(), 255);
+
+    let err = FighterBundle::new("custom").cloned_layouts(256).entries().unwrap_err();
+    assert_eq!(err, BundleError::TooManyLayouts(256));
+    assert_eq!(err.to_string(), "256 layouts were given, but 255 is the most!");
+}
+
+#[test]
+fn song_bundle_links_every_id() {
+    mock::install();
+    use the_csk_collection_api::{Hash40Type, SongBundle};
+
+    SongBundle::new("custom_song").clone_from("a01_smb_chijyou").sli(0x1AB, 3).playlist("bgmmario").register().unwrap();
+
+    with_mock(|mock| {
+        let state = mock.state();
+        let bgm = &state.bgm_db[0];
+        let stream_set = &state.stream_set[0];
+        let assigned_info = &state.assigned_info[0];
+        let stream_property = &state.stream_property[0];
+
+        assert_eq!(bgm.ui_bgm_id, hash40("ui_bgm_custom_song").0);
+        assert_eq!(bgm.clone_from_ui_bgm_id, Some(hash40("ui_bgm_a01_smb_chijyou").0));
+        assert!(matches!(bgm.stream_set_id, Hash40Type::Overwrite(id) if id == stream_set.stream_set_id));
+        assert!(matches!(stream_set.info0, Hash40Type::Overwrite(id) if id == assigned_info.info_id));
+        assert!(matches!(assigned_info.stream_id, Hash40Type::Overwrite(id) if id == stream_property.stream_id));
+        assert!(matches!(&stream_property.data_name0, StringType::Overwrite(name) if name.as_str() == Some("custom_song")));
+
+        assert_eq!(state.sli[0].tone_name.0, hash40("bgm_custom_song").0);
+        assert_eq!((state.sli[0].nus3bank_id, state.sli[0].tone_id), (0x1AB, 3));
+        assert_eq!(state.playlists[&hash40("bgmmario").0][0].ui_bgm_id, bgm.ui_bgm_id);
+        assert_eq!(
+            state.calls,
+            [
+                "add_stream_property_entry_info",
+                "add_assigned_info_entry_info",
+                "add_stream_set_entry_info",
+                "add_bgm_db_entry_info",
+                "add_new_sli_entry",
+                "add_tracks_to_playlist"
+            ]
+        );
+    });
+}
+
+#[test]
+fn stage_bundle_agrees_on_ids() {
+    mock::install();
+    use the_csk_collection_api::bundle::{StageBundle, StageForm};
+    use the_csk_collection_api::{BgmPlaylistEntry, Hash40Type};
+
+    let track = BgmPlaylistEntry { ui_bgm_id: hash40("ui_bgm_custom_song").0, ..Default::default() };
+    StageBundle::new("custom_stage").clone_from("battlefield").new_playlist("bgmcustom_stage", vec![track]).register().unwrap();
+
+    with_mock(|mock| {
+        let state = mock.state();
+        let stage = &state.stage_db[0];
+        let (stage_place_id, ui_stage_id, data) = &state.ui_stage_resources[0];
+        assert_eq!(stage.ui_stage_id, *ui_stage_id);
+        assert_eq!(stage.ui_stage_id, hash40("ui_stage_custom_stage").0);
+        assert_eq!(stage.clone_from_ui_stage_id, Some(hash40("ui_stage_battlefield").0));
+        assert!(matches!(stage.stage_place_id, Hash40Type::Overwrite(id) if id == *stage_place_id));
+        assert_eq!(*stage_place_id, hash40("custom_stage").0);
+        assert!(matches!(stage.bgm_set_id, Hash40Type::Overwrite(id) if id == hash40("bgmcustom_stage").0));
+
+        assert_eq!(data.normal.stage_load_group_hash, hash40("stage/custom_stage/normal").0);
+        assert_eq!(data.battle.stage_load_group_hash, hash40(StageForm::Battlefield.path("custom_stage").as_str()).0);
+        assert_eq!(data.end.stage_load_group_hash, hash40("stage/custom_stage/end").0);
+        assert_eq!(data.normal.nus3audio_path_hash, hash40("sound/bank/stage/se_stage_custom_stage.nus3audio").0);
+        assert_eq!(state.playlists[&hash40("bgmcustom_stage").0].len(), 1);
+        assert_eq!(state.calls[0], "add_tracks_to_playlist");
+    });
+}
+
+#[test]
+#[should_panic(expected = "play_bgm is only exported by the CSK Collection plugin on console!")]
+fn the_plugin_is_back_once_reset() {
+    mock::install();
+    the_csk_collection_api::backend::reset_backend();
+    the_csk_collection_api::play_bgm(hash40("ui_bgm_custom").0);
+}
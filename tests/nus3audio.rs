@@ -0,0 +1,188 @@
+use the_csk_collection_api::nus3audio::{self, AudioFile, Nus3audio, Nus3audioError, SyncError};
+use the_csk_collection_api::nus3bank::{Nus3bank, Nus3bankError};
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn sample_audio() -> Nus3audio {
+    let mut audio = Nus3audio::new();
+    audio.add("bgm_custom_a", b"OPUS a".to_vec()).unwrap();
+    audio.add("bgm_custom_b", vec![0xAB; 0x21]).unwrap();
+    audio.add("bgm_custom_empty", Vec::new()).unwrap();
+    audio
+}
+
+#[test]
+fn archives_round_trip() {
+    let audio = sample_audio();
+    let bytes = audio.to_bytes();
+    assert_eq!(&bytes[..4], b"NUS3");
+    assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+    assert_eq!(&bytes[8..16], b"AUDIINDX");
+    assert_eq!(u32_at(&bytes, 20), 3);
+
+    let parsed = Nus3audio::parse(&bytes).unwrap();
+    assert_eq!(parsed, audio);
+    assert_eq!(parsed.names().collect::<Vec<_>>(), ["bgm_custom_a", "bgm_custom_b", "bgm_custom_empty"]);
+    assert_eq!(parsed.get_by_id(1).map(|file| file.data.len()), Some(0x21));
+    assert_eq!(parsed.to_bytes(), bytes);
+
+    // ADOF is the third column, after TNID and NMOF
+    let adof = 0x18 + 8 + 3 * 4 + 8 + 3 * 4;
+    assert_eq!(&bytes[adof..adof + 4], b"ADOF");
+    for index in 0..3 {
+        assert_eq!(u32_at(&bytes, adof + 8 + index * 8) % 0x10, 0);
+    }
+    assert!(Nus3audio::parse(&Nus3audio::new().to_bytes()).unwrap().files.is_empty());
+}
+
+#[test]
+fn files_are_added_replaced_and_removed() {
+    let mut audio = sample_audio();
+    assert_eq!(audio.add("bgm_custom_a", Vec::new()), Err(Nus3audioError::DuplicateFile("bgm_custom_a".to_string())));
+    audio.replace("bgm_custom_a", vec![1; 0x40]).unwrap();
+    assert_eq!(audio.get("bgm_custom_a").unwrap().data, vec![1; 0x40]);
+    assert_eq!(audio.replace("bgm_missing", Vec::new()), Err(Nus3audioError::FileNotFound("bgm_missing".to_string())));
+
+    // The removed file's id stays taken, so the others keep theirs
+    let removed = audio.remove("bgm_custom_a").unwrap();
+    assert_eq!(removed.id, 0);
+    assert_eq!(
+        audio.files.iter().map(|file| (file.id, file.name.as_str(), file.data.len())).collect::<Vec<_>>(),
+        [(0, "", 0), (1, "bgm_custom_b", 0x21), (2, "bgm_custom_empty", 0)]
+    );
+    assert_eq!(audio.add("bgm_custom_c", vec![2; 3]), Ok(3));
+    assert_eq!(audio.rename("bgm_custom_c", "bgm_custom_b"), Err(Nus3audioError::DuplicateFile("bgm_custom_b".to_string())));
+    audio.rename("bgm_custom_c", "bgm_custom_d").unwrap();
+
+    let parsed = Nus3audio::parse(&audio.to_bytes()).unwrap();
+    assert_eq!(parsed.get("bgm_custom_d"), Some(&AudioFile { id: 3, name: "bgm_custom_d".to_string(), data: vec![2; 3] }));
+    assert_eq!(parsed, audio);
+
+    audio.files.push(AudioFile { id: u32::MAX, name: "bgm_custom_last".to_string(), data: Vec::new() });
+    assert_eq!(audio.add("bgm_custom_e", Vec::new()), Err(Nus3audioError::IdOverflow));
+}
+
+#[test]
+fn malformed_archives_are_refused() {
+    let bytes = sample_audio().to_bytes();
+    let mut bad = bytes.clone();
+    bad[..4].copy_from_slice(b"NUS4");
+    assert!(matches!(Nus3audio::parse(&bad), Err(Nus3audioError::BadMagic { .. })));
+    assert!(matches!(Nus3audio::parse(&bytes[..bytes.len() - 0x10]), Err(Nus3audioError::Truncated { .. })));
+
+    // Renaming TNID leaves the ids missing
+    let mut bad = bytes.clone();
+    bad[0x18..0x1C].copy_from_slice(b"XXXX");
+    assert_eq!(Nus3audio::parse(&bad), Err(Nus3audioError::MissingSection(*b"TNID")));
+
+    // NMOF renamed to TNID, the first one isn't silently replaced
+    let mut bad = bytes.clone();
+    bad[0x2C..0x30].copy_from_slice(b"TNID");
+    assert_eq!(Nus3audio::parse(&bad), Err(Nus3audioError::DuplicateSection(*b"TNID")));
+}
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn tone(tone_name: &str, meta: &[u32]) -> Vec<u8> {
+    let mut out = u32s(&[0x2, 0x0, 0x1]);
+    out.push(tone_name.len() as u8 + 1);
+    out.extend_from_slice(tone_name.as_bytes());
+    out.push(0);
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+    out.extend(u32s(&[0, 8, 0, 0x22E8]));
+    out.extend(u32s(meta));
+    out.extend(u32s(&[0, 0xFFFFFFFF, 0, 0xFFFFFFFF, 0, 0xFFFFFFFF, 0, 0xFFFFFFFF]));
+    out
+}
+
+// A nus3bank with only a TONE and a PACK section, tones laid out like tests/nus3bank.rs builds them
+fn voice_bank() -> Vec<u8> {
+    let tones = [tone("vc_custom_appeal01", &[0xAA, 0xBB]), tone("vc_custom_win01", &[0xCC]), tone("vc_abc", &[0xDD])];
+    let mut table = u32s(&[tones.len() as u32]);
+    let mut offset = 4 + tones.len() as u32 * 8;
+    for entry in tones.iter() {
+        table.extend(u32s(&[offset, entry.len() as u32]));
+        offset += entry.len() as u32;
+    }
+    tones.iter().for_each(|entry| table.extend_from_slice(entry));
+    let sections = [(b"TONE", table), (b"PACK", vec![1, 2, 3, 4])];
+
+    let mut body = b"BANKTOC ".to_vec();
+    body.extend(u32s(&[4 + sections.len() as u32 * 8, sections.len() as u32]));
+    for (magic, data) in sections.iter() {
+        body.extend_from_slice(*magic);
+        body.extend(u32s(&[data.len() as u32]));
+    }
+    for (magic, data) in sections.iter() {
+        body.extend_from_slice(*magic);
+        body.extend(u32s(&[data.len() as u32]));
+        body.extend_from_slice(data);
+    }
+    let mut out = b"NUS3".to_vec();
+    out.extend(u32s(&[body.len() as u32]));
+    out.extend(body);
+    out
+}
+
+fn voice_audio(names: &[&str]) -> Nus3audio {
+    let mut audio = Nus3audio::new();
+    for name in names {
+        audio.add(name, name.as_bytes().to_vec()).unwrap();
+    }
+    audio
+}
+
+#[test]
+fn tones_are_added_to_both_files() {
+    let audio = voice_audio(&["vc_custom_appeal01", "vc_custom_win01", "vc_abc"]).to_bytes();
+    let tones = vec![("vc_custom_win02".to_string(), vec![7; 5]), ("vc_custom_win03".to_string(), vec![8; 3])];
+    let (bank, audio) = nus3audio::append_entries_to_nus3bank_and_nus3audio(&voice_bank(), &audio, "vc_custom_win01", &tones).unwrap();
+
+    let bank = Nus3bank::parse(&bank).unwrap();
+    let audio = Nus3audio::parse(&audio).unwrap();
+    assert_eq!(bank.tone_section().unwrap().position("vc_custom_win03"), Some(4));
+    assert_eq!(audio.get("vc_custom_win03").map(|file| (file.id, file.data.clone())), Some((4, vec![8; 3])));
+    assert_eq!(nus3audio::check_sync(&bank, &audio), Ok(()));
+}
+
+#[test]
+fn out_of_sync_files_are_refused() {
+    let mut bank = Nus3bank::parse(&voice_bank()).unwrap();
+    let tones = vec![("vc_custom_win02".to_string(), vec![7; 5])];
+
+    // The audio is missing the last tone
+    let mut audio = voice_audio(&["vc_custom_appeal01", "vc_custom_win01"]);
+    let err = nus3audio::append_tones_with_audio(&mut bank, &mut audio, "vc_custom_win01", &tones).unwrap_err();
+    assert_eq!(err, SyncError::OutOfSync { id: 2, bank: Some("vc_abc".to_string()), audio: None });
+    assert_eq!(
+        err.to_string(),
+        "Your nus3bank and nus3audio are out of sync at tone 2! nus3bank has vc_abc, nus3audio has nothing"
+    );
+
+    // Or has the tones in another order
+    let mut audio = voice_audio(&["vc_custom_win01", "vc_custom_appeal01", "vc_abc"]);
+    assert!(matches!(
+        nus3audio::append_tones_with_audio(&mut bank, &mut audio, "vc_custom_win01", &tones),
+        Err(SyncError::OutOfSync { id: 0, .. })
+    ));
+
+    // Names already taken leave both untouched
+    let mut audio = voice_audio(&["vc_custom_appeal01", "vc_custom_win01", "vc_abc"]);
+    let taken = vec![("vc_custom_win02".to_string(), Vec::new()), ("vc_abc".to_string(), Vec::new())];
+    assert_eq!(
+        nus3audio::append_tones_with_audio(&mut bank, &mut audio, "vc_custom_win01", &taken),
+        Err(SyncError::Bank(Nus3bankError::DuplicateTone("vc_abc".to_string())))
+    );
+    assert_eq!(
+        nus3audio::append_tones_with_audio(&mut bank, &mut audio, "vc_missing", &tones),
+        Err(SyncError::Bank(Nus3bankError::SourceToneMissing("vc_missing".to_string())))
+    );
+    assert_eq!(bank.tone_section().unwrap().tones.len(), 3);
+    assert_eq!(audio.files.len(), 3);
+}
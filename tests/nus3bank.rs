@@ -0,0 +1,354 @@
+use std::io::Cursor;
+
+use the_csk_collection_api::nus3bank::{Nus3bank, Nus3bankError, Section, ToneEntry};
+
+fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn name(name: &str) -> Vec<u8> {
+    let mut out = vec![name.len() as u8 + 1];
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out
+}
+
+fn tone(tone_name: &str, meta: &[u32]) -> Vec<u8> {
+    let mut out = u32s(&[0x2, 0x0, 0x1]);
+    out.extend(name(tone_name));
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+    out.extend(u32s(&[0, 8, 0, 0x22E8]));
+    out.extend(u32s(meta));
+    out.extend(u32s(&[0, 0xFFFFFFFF, 0, 0xFFFFFFFF, 0, 0xFFFFFFFF, 0, 0xFFFFFFFF]));
+    out
+}
+
+fn table(entries: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = u32s(&[entries.len() as u32]);
+    let mut offset = 4 + entries.len() as u32 * 8;
+    for entry in entries {
+        out.extend(u32s(&[offset, entry.len() as u32]));
+        offset += entry.len() as u32;
+    }
+    for entry in entries {
+        out.extend_from_slice(entry);
+    }
+    out
+}
+
+fn bank(sections: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(b"BANKTOC ");
+    body.extend(u32s(&[4 + sections.len() as u32 * 8, sections.len() as u32]));
+    for (magic, data) in sections {
+        body.extend_from_slice(*magic);
+        body.extend(u32s(&[data.len() as u32]));
+    }
+    for (magic, data) in sections {
+        body.extend_from_slice(*magic);
+        body.extend(u32s(&[data.len() as u32]));
+        body.extend_from_slice(data);
+    }
+    let mut out = b"NUS3".to_vec();
+    out.extend(u32s(&[body.len() as u32]));
+    out.extend(body);
+    out
+}
+
+fn sample_bank() -> Vec<u8> {
+    let mut binf = u32s(&[0, 3]);
+    binf.extend(name("vc_custom"));
+    while binf.len() % 4 != 0 {
+        binf.push(0);
+    }
+    binf.extend(u32s(&[0x1234]));
+
+    bank(&[
+        (b"PROP", u32s(&[0, 0, 0x30003, 0x5])),
+        (b"BINF", binf),
+        (b"GRP ", table(&[u32s(&[1, 2, 3])])),
+        (b"DTON", table(&[u32s(&[4, 5]), u32s(&[6])])),
+        (
+            b"TONE",
+            table(&[
+                tone("vc_custom_appeal01", &[0xAA, 0xBB]),
+                tone("vc_custom_win01", &[0xCC]),
+                tone("vc_abc", &[0xDD, 0xEE, 0xFF]),
+            ]),
+        ),
+        (b"JUNK", vec![0; 4]),
+        (b"PACK", vec![1, 2, 3, 4, 5, 6, 7, 8]),
+    ])
+}
+
+#[test]
+fn round_trip_is_byte_identical() {
+    let data = sample_bank();
+    let parsed = Nus3bank::parse(&data).unwrap();
+    assert_eq!(parsed.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn sections_are_typed() {
+    let parsed = Nus3bank::parse(&sample_bank()).unwrap();
+    let magics: Vec<[u8; 4]> = parsed.sections.iter().map(Section::magic).collect();
+    assert_eq!(magics, [*b"PROP", *b"BINF", *b"GRP ", *b"DTON", *b"TONE", *b"JUNK", *b"PACK"]);
+    assert!(parsed.sections.iter().all(|section| !matches!(section, Section::Unknown { .. })));
+
+    let binf = parsed.binf().unwrap();
+    assert_eq!(binf.name, "vc_custom");
+    assert_eq!(binf.id, 0x1234);
+
+    let names: Vec<Option<&str>> = parsed.tone_section().unwrap().tones.iter().map(ToneEntry::name).collect();
+    assert_eq!(names, [Some("vc_custom_appeal01"), Some("vc_custom_win01"), Some("vc_abc")]);
+}
+
+#[test]
+fn unknown_sections_round_trip() {
+    let data = bank(&[(b"ABCD", vec![9; 12]), (b"TONE", table(&[tone("vc_a", &[1])]))]);
+    let parsed = Nus3bank::parse(&data).unwrap();
+    assert!(matches!(parsed.sections[0], Section::Unknown { magic, .. } if &magic == b"ABCD"));
+    assert_eq!(parsed.to_bytes().unwrap(), data);
+}
+
+// Laid out by hand rather than with the helpers above: BINF "vc", then a TONE holding the tone vc_a and
+// an empty tone stored with offset 0 instead of where it would start
+#[rustfmt::skip]
+const HAND_BANK: &[u8] = &[
+    b'N', b'U', b'S', b'3', 0x9C, 0x00, 0x00, 0x00,
+    b'B', b'A', b'N', b'K', b'T', b'O', b'C', b' ', 0x14, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+    b'B', b'I', b'N', b'F', 0x10, 0x00, 0x00, 0x00,
+    b'T', b'O', b'N', b'E', 0x5C, 0x00, 0x00, 0x00,
+    // BINF: unk0, flags, name, id
+    b'B', b'I', b'N', b'F', 0x10, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x03, b'v', b'c', 0x00, 0x34, 0x12, 0x00, 0x00,
+    // TONE: count, (offset, size) x 2
+    b'T', b'O', b'N', b'E', 0x5C, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // vc_a: header, name padded to 4, stream offset/size block, sub metadata marker, metadata, closing pairs
+    0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+    0x05, b'v', b'c', b'_', b'a', 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE8, 0x22, 0x00, 0x00,
+    0xCC, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// Where the TONE table starts in HAND_BANK
+const HAND_TONE_TABLE: usize = 0x48;
+
+#[test]
+fn hand_laid_bank_round_trips() {
+    let mut parsed = Nus3bank::parse(HAND_BANK).unwrap();
+    assert!(parsed.sections.iter().all(|section| !matches!(section, Section::Unknown { .. })));
+    assert_eq!((parsed.binf().unwrap().name.as_str(), parsed.binf().unwrap().id), ("vc", 0x1234));
+    let tones = parsed.tone_section().unwrap();
+    assert_eq!(tones.tones[1], ToneEntry::Raw(Vec::new()));
+    assert_eq!(tones.get("vc_a").unwrap().sub_meta().unwrap()[..4], [0xCC, 0, 0, 0]);
+    assert_eq!(parsed.to_bytes().unwrap(), HAND_BANK);
+
+    // The empty tone keeps its offset of 0, the new one goes after vc_a
+    parsed.append_tones("vc_a", &["vc_b".to_string()]).unwrap();
+    let output = parsed.to_bytes().unwrap();
+    let table = u32s(&[3, 0x1C, 0x48, 0, 0, 0x64, 0x48]);
+    assert_eq!(output[HAND_TONE_TABLE..HAND_TONE_TABLE + table.len()], table);
+    assert_eq!(tone_names(&output), ["vc_a", "", "vc_b"]);
+
+    // A tone emptied here starts where the next one would
+    parsed.remove_tones(&["vc_a".to_string()]).unwrap();
+    let output = parsed.to_bytes().unwrap();
+    let table = u32s(&[3, 0x1C, 0, 0, 0, 0x1C, 0x48]);
+    assert_eq!(output[HAND_TONE_TABLE..HAND_TONE_TABLE + table.len()], table);
+}
+
+#[test]
+fn long_names_are_refused_when_written() {
+    let mut parsed = Nus3bank::parse(HAND_BANK).unwrap();
+    let long_name = "a".repeat(255);
+    parsed.tone_section_mut().unwrap().get_mut("vc_a").unwrap().name = long_name.clone();
+    assert_eq!(parsed.to_bytes(), Err(Nus3bankError::NameTooLong(long_name)));
+
+    let mut parsed = Nus3bank::parse(HAND_BANK).unwrap();
+    parsed.tone_section_mut().unwrap().get_mut("vc_a").unwrap().name = "a".repeat(254);
+    assert!(parsed.to_bytes().is_ok());
+}
+
+#[test]
+fn raw_tone_sections_are_not_edited() {
+    let mut bank = Nus3bank { sections: vec![Section::Unknown { magic: *b"TONE", data: vec![0; 4] }], trailing: Vec::new() };
+    let err = bank.append_tones("vc_a", &["vc_b".to_string()]).unwrap_err();
+    assert_eq!(err, Nus3bankError::UnsupportedSection(*b"TONE"));
+    assert_eq!(err.to_string(), "The TONE section of your nus3bank can't be rebuilt, so it can't be edited!");
+    assert_eq!(bank.remove_tones(&["vc_a".to_string()]), Err(Nus3bankError::UnsupportedSection(*b"TONE")));
+    assert_eq!(bank.rename_tone("vc_a", "vc_b"), Err(Nus3bankError::UnsupportedSection(*b"TONE")));
+    assert_eq!(bank.replace_tone_meta("vc_a", "vc_b"), Err(Nus3bankError::UnsupportedSection(*b"TONE")));
+}
+
+#[test]
+fn appended_tones_reserialize_with_fixed_sizes() {
+    let mut data = sample_bank();
+    let new_entries = vec!["vc_custom_appeal02".to_string(), "vc_custom_win".to_string()];
+    let output = the_csk_collection_api::append_entries_to_nus3bank(&mut data, "vc_custom_win01", &new_entries).unwrap();
+
+    let parsed = Nus3bank::parse(&output).unwrap();
+    assert_eq!(u32::from_le_bytes(output[4..8].try_into().unwrap()) as usize, output.len() - 8);
+    let tones = parsed.tone_section().unwrap();
+    assert_eq!(tones.tones.len(), 5);
+    let source = tones.get("vc_custom_win01").unwrap();
+    for name in new_entries.iter() {
+        let tone = tones.get(name).unwrap();
+        assert_eq!(tone.header, source.header);
+        assert_eq!(tone.sub_meta(), source.sub_meta());
+    }
+    assert_eq!(parsed.to_bytes().unwrap(), output);
+}
+
+fn tone_names(data: &[u8]) -> Vec<String> {
+    let parsed = Nus3bank::parse(data).unwrap();
+    parsed.tone_section().unwrap().tones.iter().map(|tone| tone.name().unwrap_or_default().to_string()).collect()
+}
+
+#[test]
+fn removed_tones_are_gone() {
+    let output = the_csk_collection_api::remove_entries_from_nus3bank(&sample_bank(), &["vc_custom_win01".to_string()]).unwrap();
+    // The removed tone's slot stays, empty, so vc_abc keeps its tone id
+    assert_eq!(tone_names(&output), ["vc_custom_appeal01", "", "vc_abc"]);
+    let parsed = Nus3bank::parse(&output).unwrap();
+    assert_eq!(parsed.tone_section().unwrap().tones[1], ToneEntry::Raw(Vec::new()));
+    assert_eq!(parsed.tone_section().unwrap().position("vc_abc"), Some(2));
+    assert_eq!(parsed.to_bytes().unwrap(), output);
+    assert_eq!(
+        the_csk_collection_api::remove_entries_from_nus3bank(&sample_bank(), &["vc_missing".to_string()]),
+        Err(Nus3bankError::ToneNotFound("vc_missing".to_string()))
+    );
+}
+
+#[test]
+fn renamed_tone_keeps_its_data() {
+    let output = the_csk_collection_api::rename_entry_in_nus3bank(&sample_bank(), "vc_abc", "vc_abcdefgh").unwrap();
+    assert_eq!(tone_names(&output), ["vc_custom_appeal01", "vc_custom_win01", "vc_abcdefgh"]);
+    let before = Nus3bank::parse(&sample_bank()).unwrap();
+    let after = Nus3bank::parse(&output).unwrap();
+    assert_eq!(after.tone_section().unwrap().get("vc_abcdefgh").unwrap().body, before.tone_section().unwrap().get("vc_abc").unwrap().body);
+    assert_eq!(
+        the_csk_collection_api::rename_entry_in_nus3bank(&sample_bank(), "vc_abc", "vc_custom_win01"),
+        Err(Nus3bankError::DuplicateTone("vc_custom_win01".to_string()))
+    );
+}
+
+#[test]
+fn replaced_meta_matches_source() {
+    let output =
+        the_csk_collection_api::replace_entry_meta_in_nus3bank(&sample_bank(), "vc_custom_win01", "vc_abc").unwrap();
+    let parsed = Nus3bank::parse(&output).unwrap();
+    let tones = parsed.tone_section().unwrap();
+    assert_eq!(tones.get("vc_custom_win01").unwrap().sub_meta(), tones.get("vc_abc").unwrap().sub_meta());
+    assert_eq!(tone_names(&output), tone_names(&sample_bank()));
+}
+
+#[test]
+fn errors_are_typed() {
+    let mut data = sample_bank();
+    data[0] = b'X';
+    assert_eq!(Nus3bank::parse(&data), Err(Nus3bankError::BadMagic { expected: *b"NUS3", found: *b"XUS3" }));
+
+    let mut data = sample_bank();
+    data[8] = b'X';
+    assert_eq!(Nus3bank::parse(&data), Err(Nus3bankError::MissingToc));
+
+    let mut data = sample_bank();
+    data[16] = 0xFF;
+    assert!(matches!(Nus3bank::parse(&data), Err(Nus3bankError::SizeMismatch { .. })));
+
+    let mut data = sample_bank();
+    data[4] += 1;
+    assert_eq!(
+        Nus3bank::parse(&data),
+        Err(Nus3bankError::SizeMismatch { expected: data.len() as u64 - 8, found: data.len() as u64 - 7 })
+    );
+
+    // A broken known section fails the whole bank instead of turning into an unknown one
+    let data = bank(&[(b"GRP ", u32s(&[1, 0x10, 4, 0]))]);
+    assert_eq!(Nus3bank::parse(&data), Err(Nus3bankError::SizeMismatch { expected: 0xC, found: 0x10 }));
+    let data = bank(&[(b"BINF", u32s(&[0, 3, 0]))]);
+    assert_eq!(Nus3bank::parse(&data), Err(Nus3bankError::InvalidName { offset: 8 }));
+
+    let mut tone_data = u32s(&[0x2, 0x0, 0x1]);
+    tone_data.extend(name("vc_nometa"));
+    tone_data.push(0);
+    tone_data.extend(u32s(&[0, 8, 0, 0]));
+    let mut bank = Nus3bank::parse(&bank(&[(b"TONE", table(&[tone_data]))])).unwrap();
+    let tone = bank.tone_section_mut().unwrap().get_mut("vc_nometa").unwrap();
+    assert_eq!(tone.sub_meta(), Err(Nus3bankError::MissingSubMeta("vc_nometa".to_string())));
+    assert_eq!(tone.set_sub_meta(&[]), Err(Nus3bankError::MissingSubMeta("vc_nometa".to_string())));
+
+    let mut data = sample_bank();
+    assert_eq!(
+        the_csk_collection_api::append_entries_to_nus3bank(&mut data, "vc_missing", &vec!["vc_new".to_string()]),
+        Err(Nus3bankError::SourceToneMissing("vc_missing".to_string()))
+    );
+    let long_name = "a".repeat(255);
+    assert_eq!(
+        the_csk_collection_api::append_entries_to_nus3bank(&mut data, "vc_abc", &vec![long_name.clone()]),
+        Err(Nus3bankError::NameTooLong(long_name))
+    );
+}
+
+#[test]
+fn truncated_banks_report_truncation() {
+    let data = sample_bank();
+    for len in 0..data.len() {
+        match Nus3bank::parse(&data[..len]) {
+            Err(Nus3bankError::Truncated { offset }) => assert!(offset <= len as u64),
+            other => panic!("Parsing {} of {} bytes returned {:?}", len, data.len(), other),
+        }
+    }
+}
+
+#[test]
+fn read_helpers_report_truncation() {
+    let mut data = [1, 2, 3];
+    assert_eq!(
+        the_csk_collection_api::read_u32(&mut Cursor::new(&mut data[..])),
+        Err(Nus3bankError::Truncated { offset: 0 })
+    );
+
+    // The marker is there but the closing pairs never are
+    let mut data = [0xE8, 0x22, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    assert_eq!(
+        the_csk_collection_api::get_sub_meta_offset_and_size(&mut Cursor::new(&mut data[..])),
+        Err(Nus3bankError::Truncated { offset: 12 })
+    );
+}
+
+// Flips random bytes all over a valid bank. Nothing may panic, and whatever still parses has to write back the same.
+#[test]
+fn fuzzed_banks_do_not_panic() {
+    let original = sample_bank();
+    let mut state: u64 = 0x2545F4914F6CDD1D;
+    let mut next = || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    for _ in 0..2000 {
+        let mut data = original.clone();
+        for _ in 0..1 + next() % 4 {
+            let index = next() % data.len();
+            data[index] = next() as u8;
+        }
+        if next() % 4 == 0 {
+            data.truncate(next() % data.len());
+        }
+
+        if let Ok(bank) = Nus3bank::parse(&data) {
+            assert_eq!(bank.to_bytes().unwrap(), data);
+        }
+        let _ = the_csk_collection_api::append_entries_to_nus3bank(&mut data.clone(), "vc_abc", &vec!["vc_new".to_string()]);
+        let _ = the_csk_collection_api::remove_entries_from_nus3bank(&data, &["vc_custom_win01".to_string()]);
+        let _ = the_csk_collection_api::rename_entry_in_nus3bank(&data, "vc_abc", "vc_abd");
+        let _ = the_csk_collection_api::replace_entry_meta_in_nus3bank(&data, "vc_abc", "vc_custom_win01");
+        let _ = the_csk_collection_api::get_sub_meta_offset_and_size(&mut Cursor::new(&mut data[..]));
+    }
+}
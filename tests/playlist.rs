@@ -0,0 +1,90 @@
+use the_csk_collection_api::playlist::{self, PlaylistSlot, PlaylistTrack};
+use the_csk_collection_api::{hash40, BgmPlaylistEntry, ShortType, UnsignedShortType};
+
+fn track(name: &str, order: i16, incidence: u16) -> BgmPlaylistEntry {
+    let mut track = BgmPlaylistEntry::new(name);
+    track.set_slots(std::array::from_fn(|_| PlaylistSlot::new(order, incidence)));
+    track
+}
+
+#[test]
+fn slots_map_to_the_fields() {
+    let mut entry = BgmPlaylistEntry::new("ui_bgm_custom").order3(7).incidence15(300);
+    entry.order5 = ShortType::Optional(Some(2));
+    let track = PlaylistTrack::from(entry);
+    assert!(matches!(track.slots[3].order, ShortType::Overwrite(7)));
+    assert!(matches!(track.slots[5].order, ShortType::Optional(Some(2))));
+    assert!(matches!(track.slots[15].incidence, UnsignedShortType::Overwrite(300)));
+    assert!(!track.slots[0].order.is_set());
+
+    let mut entry = BgmPlaylistEntry::from(track);
+    assert_eq!(entry.ui_bgm_id, hash40("ui_bgm_custom").0);
+    assert!(matches!(entry.order3, ShortType::Overwrite(7)));
+    assert!(matches!(entry.incidence15, UnsignedShortType::Overwrite(300)));
+
+    entry.set_incidence(500);
+    assert!(entry.slots().iter().all(|slot| matches!(slot.incidence, UnsignedShortType::Overwrite(500))));
+}
+
+#[test]
+fn appended_tracks_go_last() {
+    let existing = [track("ui_bgm_a", 0, 100), track("ui_bgm_b", 4, 100), track("ui_bgm_c", -1, 0)];
+    assert_eq!(playlist::next_order(&existing, 0), 5);
+    assert_eq!(playlist::next_order(&[], 0), 0);
+
+    let mut tracks = [BgmPlaylistEntry::new("ui_bgm_d").order1(2), BgmPlaylistEntry::new("ui_bgm_e")];
+    playlist::append_orders(&existing, &mut tracks);
+    assert!(matches!(tracks[0].order0, ShortType::Overwrite(5)));
+    assert!(matches!(tracks[1].order0, ShortType::Overwrite(6)));
+    // Set orders are kept
+    assert!(matches!(tracks[0].order1, ShortType::Overwrite(2)));
+    assert!(matches!(tracks[1].order1, ShortType::Overwrite(5)));
+}
+
+#[test]
+fn incidence_is_rescaled() {
+    let mut tracks = [track("ui_bgm_a", 0, 40000), track("ui_bgm_b", 1, 40000), track("ui_bgm_c", 2, 20000)];
+    tracks[2].incidence4 = UnsignedShortType::Optional(None);
+    playlist::rescale_incidence(&mut tracks, 65535);
+    for slot in 0..playlist::PLAYLIST_SLOTS {
+        assert!(playlist::total_incidence(&tracks, slot) <= 65535);
+    }
+    assert!(matches!(tracks[0].incidence0, UnsignedShortType::Overwrite(26214)));
+    assert!(matches!(tracks[2].incidence0, UnsignedShortType::Overwrite(13107)));
+    // Slot 4 only adds up to 80000
+    assert!(matches!(tracks[0].incidence4, UnsignedShortType::Overwrite(32767)));
+    assert!(!tracks[2].incidence4.is_set());
+
+    let mut tracks = [track("ui_bgm_a", 0, 100)];
+    playlist::rescale_incidence(&mut tracks, 1000);
+    assert!(matches!(tracks[0].incidence0, UnsignedShortType::Overwrite(100)));
+
+    // Rare tracks stay in, tracks that are never picked stay out
+    let mut tracks = [track("ui_bgm_a", 0, 1), track("ui_bgm_b", 1, 60000), track("ui_bgm_c", 2, 0)];
+    playlist::rescale_incidence(&mut tracks, 30000);
+    assert!(matches!(tracks[0].incidence0, UnsignedShortType::Overwrite(1)));
+    assert!(matches!(tracks[1].incidence0, UnsignedShortType::Overwrite(29999)));
+    assert!(matches!(tracks[2].incidence0, UnsignedShortType::Overwrite(0)));
+
+    let mut tracks = [track("ui_bgm_a", 0, 1), track("ui_bgm_b", 1, 1), track("ui_bgm_c", 2, 98)];
+    playlist::rescale_incidence(&mut tracks, 10);
+    let incidences: Vec<_> = tracks.iter().map(|track| track.incidence0.value().copied()).collect();
+    assert_eq!(incidences, [Some(1), Some(1), Some(8)]);
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn tracks_are_appended_after_the_existing_ones() {
+    use the_csk_collection_api::backend::mock::{self, with_mock};
+
+    mock::install();
+    let playlist = hash40("bgmmario").0;
+    let existing = [track("ui_bgm_a", 0, 100), track("ui_bgm_b", 1, 100)];
+    playlist::append_tracks_to_playlist(playlist, &existing, vec![BgmPlaylistEntry::new("ui_bgm_custom")]);
+
+    with_mock(|mock| {
+        let tracks = &mock.state().playlists[&playlist];
+        assert_eq!(tracks.len(), 1);
+        assert!(matches!(tracks[0].order0, ShortType::Overwrite(2)));
+    });
+}
@@ -0,0 +1,182 @@
+use the_csk_collection_api::convert;
+use the_csk_collection_api::registry::{Conflict, Registry, Slot, Table};
+use the_csk_collection_api::{
+    hash40, BgmDatabaseRootEntry, CharacterDatabaseEntry, Hash40Type, ShortType, SignedByteType, StreamSetEntry,
+};
+
+fn chara(name: &str, save_no: i8, disp_order: i8) -> CharacterDatabaseEntry {
+    CharacterDatabaseEntry {
+        ui_chara_id: hash40(name).0,
+        save_no: SignedByteType::Overwrite(save_no),
+        disp_order: SignedByteType::Optional(Some(disp_order)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn duplicate_ids_conflict() {
+    let mut registry = Registry::new();
+    let bgm = BgmDatabaseRootEntry { ui_bgm_id: hash40("ui_bgm_custom").0, ..Default::default() };
+    assert!(registry.record(&bgm).is_empty());
+    assert_eq!(registry.record(&bgm), [Conflict::DuplicateId { table: Table::Bgm, id: hash40("ui_bgm_custom") }]);
+
+    // Ids only collide within their own table
+    let stream_set = StreamSetEntry { stream_set_id: hash40("ui_bgm_custom").0, ..Default::default() };
+    assert!(registry.record(&stream_set).is_empty());
+    assert!(registry.contains(Table::StreamSet, hash40("ui_bgm_custom").0));
+    assert_eq!(registry.conflicts().len(), 1);
+}
+
+#[test]
+fn shared_slots_conflict() {
+    let mut registry = Registry::new();
+    assert!(registry.record(&chara("ui_chara_first", 100, 90)).is_empty());
+    assert_eq!(
+        registry.record(&chara("ui_chara_second", 100, 91)),
+        [Conflict::SlotTaken {
+            table: Table::Chara,
+            slot: Slot::SaveNo,
+            value: 100,
+            owner: hash40("ui_chara_first"),
+            id: hash40("ui_chara_second"),
+        }]
+    );
+    // -1 means no slot
+    assert!(registry.record(&chara("ui_chara_third", -1, -1)).is_empty());
+    assert!(registry.record(&chara("ui_chara_fourth", -1, -1)).is_empty());
+    assert_eq!(registry.owner(Table::Chara, Slot::DispOrder, 91), Some(Some(hash40("ui_chara_second").0)));
+}
+
+// Two vanilla ui_chara_db rows and one ui_bgm_db row, as paracobNET exports them
+const VANILLA_PARAMS: &str = r#"<struct>
+  <list hash="db_root">
+    <struct index="0">
+      <hash40 hash="ui_chara_id">ui_chara_mario</hash40>
+      <hash40 hash="fighter_kind">fighter_kind_mario</hash40>
+      <sbyte hash="disp_order">0</sbyte>
+      <sbyte hash="save_no">0</sbyte>
+    </struct>
+    <struct index="1">
+      <hash40 hash="ui_chara_id">ui_chara_donkey</hash40>
+      <hash40 hash="fighter_kind">fighter_kind_donkey</hash40>
+      <sbyte hash="disp_order">-1</sbyte>
+      <sbyte hash="save_no">1</sbyte>
+    </struct>
+    <struct index="2">
+      <hash40 hash="ui_bgm_id">ui_bgm_a01_smb_chijyou</hash40>
+      <hash40 hash="stream_set_id">set_a01_smb_chijyou</hash40>
+      <short hash="save_no">0</short>
+      <short hash="test_disp_order">0</short>
+      <int hash="menu_value">0</int>
+    </struct>
+  </list>
+</struct>"#;
+
+#[test]
+fn vanilla_params_take_their_slots() {
+    let mut registry = Registry::new();
+    registry.record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+    assert_eq!(registry.owner(Table::Chara, Slot::SaveNo, 1), Some(Some(hash40("ui_chara_donkey").0)));
+    assert_eq!(registry.owner(Table::Chara, Slot::DispOrder, 1), None);
+    assert_eq!(registry.owner(Table::Bgm, Slot::DispOrder, 0), Some(Some(hash40("ui_bgm_a01_smb_chijyou").0)));
+    assert!(!registry.contains(Table::Chara, hash40("ui_chara_mario").0));
+    assert!(registry.is_vanilla(Table::Chara, hash40("ui_chara_mario").0));
+    assert!(registry.knows_vanilla(Table::Bgm) && !registry.knows_vanilla(Table::StreamSet));
+
+    // Editing a vanilla entry keeps its slots, a new one taking them conflicts
+    assert!(registry.record(&chara("ui_chara_mario", 0, 0)).is_empty());
+    assert_eq!(
+        registry.record(&chara("ui_chara_vanilla_test", 1, 2)),
+        [Conflict::SlotTaken {
+            table: Table::Chara,
+            slot: Slot::SaveNo,
+            value: 1,
+            owner: hash40("ui_chara_donkey"),
+            id: hash40("ui_chara_vanilla_test"),
+        }]
+    );
+}
+
+#[test]
+fn vanilla_params_read_back_as_overwrites() {
+    let mut registry = Registry::new();
+    registry.record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+
+    let mario: CharacterDatabaseEntry = registry.vanilla_entry(hash40("ui_chara_mario").0).unwrap();
+    assert!(matches!(mario.fighter_kind, Hash40Type::Overwrite(kind) if kind == hash40("fighter_kind_mario").0));
+    // disp_order converts to Optional for manifests, read back it's Overwrite too
+    assert!(matches!(mario.disp_order, SignedByteType::Overwrite(0)));
+    assert!(registry.vanilla_entry::<CharacterDatabaseEntry>(hash40("ui_chara_custom").0).is_none());
+    assert!(registry.vanilla_entry::<BgmDatabaseRootEntry>(hash40("ui_chara_mario").0).is_none());
+
+    let ids: Vec<u64> = registry.vanilla_entries::<CharacterDatabaseEntry>().iter().map(|chara| chara.ui_chara_id).collect();
+    assert_eq!(ids, [hash40("ui_chara_mario").0, hash40("ui_chara_donkey").0]);
+
+    // Recording a row again replaces it
+    let mut rows = convert::parse_rows(VANILLA_PARAMS).unwrap();
+    rows.truncate(1);
+    rows[0].fields.retain(|(key, _)| *key != hash40("disp_order"));
+    registry.record_params(&rows);
+    let mario: CharacterDatabaseEntry = registry.vanilla_entry(hash40("ui_chara_mario").0).unwrap();
+    assert!(!mario.disp_order.is_set());
+    assert_eq!(registry.vanilla_entries::<CharacterDatabaseEntry>().len(), 2);
+}
+
+#[test]
+fn read_back_functions_use_the_global_registry() {
+    use the_csk_collection_api::registry;
+
+    registry::record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+    let donkey = the_csk_collection_api::get_chara_db_entry(hash40("ui_chara_donkey").0).unwrap();
+    assert!(matches!(donkey.save_no, SignedByteType::Overwrite(1)));
+    let bgm: Vec<BgmDatabaseRootEntry> = the_csk_collection_api::iter_bgm_db().collect();
+    assert_eq!(bgm.len(), 1);
+    assert!(matches!(bgm[0].save_no, ShortType::Overwrite(0)));
+    assert!(the_csk_collection_api::get_stage_db_entry(hash40("ui_stage_battlefield").0).is_none());
+}
+
+#[test]
+fn allocator_skips_used_and_vanilla_slots() {
+    let mut registry = Registry::new();
+    registry.record_params(&convert::parse_rows(VANILLA_PARAMS).unwrap());
+    assert_eq!(registry.next_free_save_no(Table::Chara), Some(2));
+    assert!(registry.record(&chara("ui_chara_first", 3, -1)).is_empty());
+    assert_eq!(registry.next_free_save_no(Table::Chara), Some(4));
+    assert_eq!(registry.next_free_disp_order(Table::Chara), Some(1));
+
+    // A reserved slot goes to the first entry using it
+    assert!(registry.record(&chara("ui_chara_second", 2, -1)).is_empty());
+    assert_eq!(registry.owner(Table::Chara, Slot::SaveNo, 2), Some(Some(hash40("ui_chara_second").0)));
+
+    assert_eq!(registry.next_free_save_no(Table::Amiibo), None);
+    assert_eq!(registry.next_free_disp_order(Table::Tips), Some(0));
+    assert_eq!(registry.next_free_disp_order(Table::Tips), Some(1));
+}
+
+#[test]
+fn allocator_runs_out() {
+    let mut registry = Registry::new();
+    for value in 0..=i8::MAX as i64 {
+        registry.reserve(Table::Series, Slot::DispOrder, value);
+    }
+    assert_eq!(registry.next_free_disp_order(Table::Series), None);
+}
+
+// Needs the mock backend to call the add_* functions
+#[cfg(feature = "mock")]
+#[test]
+fn add_functions_record_into_the_global_registry() {
+    the_csk_collection_api::backend::mock::install();
+    use the_csk_collection_api::{registry, Hash40, ShortType};
+
+    let bgm = BgmDatabaseRootEntry {
+        ui_bgm_id: hash40("ui_bgm_registry_test").0,
+        save_no: ShortType::Overwrite(30000),
+        ..Default::default()
+    };
+    the_csk_collection_api::add_bgm_db_entry_info(&bgm);
+    the_csk_collection_api::add_bgm_db_entry_info(&bgm);
+
+    assert!(registry::with_registry(|registry| registry.contains(Table::Bgm, bgm.ui_bgm_id)));
+    assert!(registry::conflicts().contains(&Conflict::DuplicateId { table: Table::Bgm, id: Hash40(bgm.ui_bgm_id) }));
+}
@@ -0,0 +1,132 @@
+use the_csk_collection_api::stream::{ConditionProcess, SoundCondition, StreamSetError, TimePoint};
+use the_csk_collection_api::builder::FieldValue;
+use the_csk_collection_api::validate::{DiagnosticKind, Validate};
+use the_csk_collection_api::{
+    hash40, AssignedInfoEntry, Hash40, Hash40Type, StreamPropertyEntry, StreamSetEntry, StringType, UnsignedShortType,
+};
+
+#[test]
+fn infos_push_and_insert() {
+    let mut set = StreamSetEntry::new("set_custom").info0("info_custom").info2(Hash40(0));
+    set.info1 = Hash40Type::Optional(Some(hash40("info_custom_b").0));
+    assert_eq!(set.infos()[..3], [Some(hash40("info_custom")), Some(hash40("info_custom_b")), None]);
+
+    assert_eq!(set.push_info(hash40("info_custom_c")), Ok(2));
+    set.insert_info(0, hash40("info_custom_first")).unwrap();
+    assert_eq!(
+        set.infos()[..5],
+        [
+            Some(hash40("info_custom_first")),
+            Some(hash40("info_custom")),
+            Some(hash40("info_custom_b")),
+            Some(hash40("info_custom_c")),
+            None
+        ]
+    );
+    // Only the infos that changed are written
+    assert!(!set.info15.is_set());
+    assert_eq!(set.insert_info(16, hash40("info_custom")), Err(StreamSetError::IndexOutOfRange(16)));
+
+    for _ in 4..16 {
+        set.push_info(hash40("info_custom_filler")).unwrap();
+    }
+    assert_eq!(set.push_info(hash40("info_custom_d")), Err(StreamSetError::Full));
+    assert_eq!(set.insert_info(3, hash40("info_custom_d")), Err(StreamSetError::Full));
+}
+
+#[test]
+fn clones_keep_the_infos_they_inherit() {
+    let mut set = StreamSetEntry::clone_from("set_a01_smb_chijyou").stream_set_id("set_custom");
+    assert_eq!(set.push_info(hash40("info_custom")), Err(StreamSetError::UnresolvedClone));
+    assert_eq!(set.insert_info(0, hash40("info_custom")), Err(StreamSetError::UnresolvedClone));
+    assert!(!set.info0.is_set() && !set.info1.is_set());
+
+    let mut infos = [None; 16];
+    infos[1] = Some(hash40("info_custom_b"));
+    set.set_infos(infos);
+    // info0 is still the one of set_a01_smb_chijyou
+    assert!(!set.info0.is_set());
+    assert!(matches!(set.info1, Hash40Type::Overwrite(hash) if hash == hash40("info_custom_b").0));
+    assert!(!set.info2.is_set());
+
+    let vanilla = StreamSetEntry::new("set_a01_smb_chijyou").info0("info_a01_smb_chijyou").info1("info_a01_smb_chijyou_b");
+    set.inherit_infos(&vanilla);
+    assert!(matches!(set.info15, Hash40Type::Overwrite(0)));
+    assert_eq!(set.push_info(hash40("info_custom_c")), Ok(2));
+    assert_eq!(
+        set.infos()[..4],
+        [Some(hash40("info_a01_smb_chijyou")), Some(hash40("info_custom_b")), Some(hash40("info_custom_c")), None]
+    );
+}
+
+#[test]
+fn conditions_are_typed() {
+    let mut info = AssignedInfoEntry::new("info_custom").condition(SoundCondition::NightStage);
+    assert!(matches!(info.condition, Hash40Type::Overwrite(hash) if hash == hash40("sound_condition_night_stage").0));
+    assert_eq!(info.get_condition(), Some(SoundCondition::NightStage));
+    assert_eq!(info.get_condition_process(), None);
+
+    info.set_condition_process(ConditionProcess::Add);
+    assert_eq!(info.get_condition_process(), Some(ConditionProcess::Add));
+    assert_eq!(ConditionProcess::Add.to_string(), "sound_condition_process_add");
+
+    let unknown = hash40("sound_condition_custom");
+    info.condition = Hash40Type::Overwrite(unknown.0);
+    assert_eq!(info.get_condition(), Some(SoundCondition::Unknown(unknown)));
+    assert_eq!(SoundCondition::from(hash40("sound_condition_none")), SoundCondition::None);
+    assert_eq!(Hash40::from(SoundCondition::Unknown(unknown)), unknown);
+    // Unknown values print by name once their label is loaded
+    assert_eq!(SoundCondition::Unknown(unknown).to_string(), format!("{:#012x}", unknown.0));
+    the_csk_collection_api::hash40::load_labels_from_str("sound_condition_custom");
+    assert_eq!(SoundCondition::Unknown(unknown).to_string(), "sound_condition_custom");
+}
+
+#[test]
+fn time_points_parse_and_print() {
+    assert_eq!("00:02:14.640".parse(), Ok(TimePoint::from_millis(134_640)));
+    assert_eq!("02:14.64".parse(), Ok(TimePoint::from_millis(134_640)));
+    assert_eq!("75:00".parse(), Ok(TimePoint::from_millis(4_500_000)));
+    for invalid in ["", "14.640", "00:60.000", "01:60:00.000", "00:01.6400", "00:-1.000", "a:01.000"] {
+        assert!(invalid.parse::<TimePoint>().is_err(), "{:?} parsed", invalid);
+    }
+    assert_eq!(TimePoint::from_millis(134_640).to_string(), "00:02:14.640");
+    assert_eq!(TimePoint::from_millis(4_500_000).to_string(), "01:15:00.000");
+
+    assert_eq!(TimePoint::from_samples(48_000 * 83 + 24_000, 48_000), TimePoint::from_millis(83_500));
+    assert_eq!(TimePoint::from_millis(83_500).to_samples(44_100), 3_682_350);
+    assert_eq!(TimePoint::from_frames(90), TimePoint::from_millis(1_500));
+    assert_eq!(TimePoint::from_millis(1_500).to_frames(), 90);
+}
+
+#[test]
+fn stream_points_are_typed() {
+    let mut stream = StreamPropertyEntry::new("stream_custom")
+        .data_name0("custom")
+        .end_point(TimePoint::from_millis(134_640))
+        .start_point_suddendeath("01:23.500".parse::<TimePoint>().unwrap());
+    assert_eq!(TimePoint::from_field(&stream.start_point_suddendeath), Ok(Some(TimePoint::from_millis(83_500))));
+    assert_eq!(TimePoint::from_field(&stream.start_point0), Ok(None));
+    assert_eq!(stream.data_names()[0].value().and_then(|name| name.as_str()), Some("custom"));
+
+    stream.set_fadeout(TimePoint::from_millis(2_000));
+    assert!(matches!(stream.fadeout_frame, UnsignedShortType::Overwrite(120)));
+    assert_eq!(stream.get_fadeout(), Some(TimePoint::from_millis(2_000)));
+    assert!(stream.validate().is_empty());
+
+    *stream.start_points_mut()[1] = TimePoint::from_millis(134_640).into_field();
+    stream.start_point_transition = StringType::Overwrite(the_csk_collection_api::CStrCSK::new("later"));
+    let kinds: Vec<_> = stream.validate().into_iter().map(|diagnostic| (diagnostic.field, diagnostic.kind)).collect();
+    assert_eq!(
+        kinds,
+        [
+            (
+                "start_point1",
+                DiagnosticKind::PastEndPoint {
+                    point: TimePoint::from_millis(134_640),
+                    end_point: TimePoint::from_millis(134_640)
+                }
+            ),
+            ("start_point_transition", DiagnosticKind::InvalidTimePoint("later".to_string())),
+        ]
+    );
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use the_csk_collection_api::validate::{has_errors, DiagnosticKind, Severity, Validate};
+use the_csk_collection_api::{
+    hash40, CharacterDatabaseEntry, CharacterLayoutDatabaseEntry, Hash40, Hash40Type, SignedByteType,
+    UnsignedByteMap, UnsignedByteType,
+};
+
+#[test]
+fn cloned_entries_only_need_an_id() {
+    let chara = CharacterDatabaseEntry {
+        ui_chara_id: hash40("ui_chara_custom").0,
+        clone_from_ui_chara_id: Some(hash40("ui_chara_mario").0),
+        ..Default::default()
+    };
+    assert!(chara.validate().is_empty());
+
+    let no_id = CharacterDatabaseEntry { ui_chara_id: 0, ..chara };
+    let diagnostics = no_id.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::MissingId);
+    assert_eq!(diagnostics[0].field, "ui_chara_id");
+}
+
+#[test]
+fn new_entries_need_their_fields() {
+    let layout = CharacterLayoutDatabaseEntry {
+        ui_layout_id: hash40("ui_layout_custom_00").0,
+        ui_chara_id: Hash40Type::Overwrite(hash40("ui_chara_custom").0),
+        ..Default::default()
+    };
+    let diagnostics = layout.validate();
+    assert!(has_errors(&diagnostics));
+    let fields: Vec<&str> = diagnostics.iter().map(|diagnostic| diagnostic.field).collect();
+    assert_eq!(fields, ["chara_color"]);
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.kind == DiagnosticKind::MissingField));
+}
+
+#[test]
+fn suspicious_values_are_warnings() {
+    let chara = CharacterDatabaseEntry {
+        ui_chara_id: hash40("ui_chara_custom").0,
+        clone_from_ui_chara_id: Some(hash40("ui_chara_mario").0),
+        save_no: SignedByteType::Overwrite(3),
+        color_num: UnsignedByteType::Overwrite(10),
+        ..Default::default()
+    };
+    // Whether the game uses the save_no is up to the registry
+    let diagnostics = chara.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(!has_errors(&diagnostics));
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert!(matches!(diagnostics[0].kind, DiagnosticKind::ColorsWithoutLayouts { color_num: 10, .. }));
+}
+
+#[test]
+fn unknown_map_keys_are_errors() {
+    let mut map = HashMap::new();
+    map.insert(hash40("c08_index").0, UnsignedByteType::Overwrite(8));
+    map.insert(hash40("n12_index").0, UnsignedByteType::Overwrite(0));
+    map.insert(hash40("c08_color").0, UnsignedByteType::Overwrite(0));
+    let chara = CharacterDatabaseEntry {
+        ui_chara_id: hash40("ui_chara_custom").0,
+        clone_from_ui_chara_id: Some(hash40("ui_chara_mario").0),
+        extra_index_maps: UnsignedByteMap::Overwrite(map),
+        ..Default::default()
+    };
+    let diagnostics = chara.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnknownMapKey(Hash40(hash40("c08_color").0)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn manifests_need_layouts_for_extra_colors() {
+    use the_csk_collection_api::manifest::Manifest;
+
+    let chara = CharacterDatabaseEntry {
+        ui_chara_id: hash40("ui_chara_custom").0,
+        clone_from_ui_chara_id: Some(hash40("ui_chara_mario").0),
+        color_num: UnsignedByteType::Overwrite(10),
+        ..Default::default()
+    };
+    let layout = |color: u8| CharacterLayoutDatabaseEntry {
+        ui_layout_id: hash40(&format!("ui_layout_custom_{:02}", color)).0,
+        clone_from_ui_layout_id: Some(hash40("ui_layout_mario_00").0),
+        ui_chara_id: Hash40Type::Overwrite(hash40("ui_chara_custom").0),
+        chara_color: UnsignedByteType::Overwrite(color),
+        ..Default::default()
+    };
+
+    let mut manifest = Manifest { chara_db: vec![chara], chara_layout_db: vec![layout(8)], ..Default::default() };
+    let diagnostics = manifest.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ColorsWithoutLayouts { color_num: 10, missing: vec![9] });
+
+    manifest.chara_layout_db.push(layout(9));
+    assert!(manifest.validate().is_empty());
+}